@@ -1,3 +1,4 @@
-// use anchor_lang::prelude::*;
+use anchor_lang::prelude::*;
 
-// #[constant]
+#[constant]
+pub const MAX_MEMO_LEN: u32 = 128; // In bytes
@@ -4,6 +4,18 @@ use anchor_lang::prelude::*;
 pub enum WrapperError {
     #[msg("Decimal provided does not match the mint's decimal value")]
     InvalidDecimals,
+    #[msg("The fee schedule is invalid")]
+    InvalidFeeSchedule,
+    #[msg("Only the fee manager can withdraw the fees")]
+    NotFeeManager,
+    #[msg("The fee collector is needed, the transfer has a fee")]
+    MissingFeeCollector,
+    #[msg("The fee collector does not belong to the wrapper or the mint")]
+    WrongFeeCollector,
+    #[msg("The wrapper only allows recipients with a valid identity")]
+    UnverifiedRecipient,
+    #[msg("The wrapper token account doesn't back the user supply and the collected fees")]
+    TreasuryNotBacked,
 }
 
 #[error_code]
@@ -13,7 +25,15 @@ pub enum TransferError {
     #[msg("Decimal provided does not match the mint's decimal value")]
     InvalidDecimals,
     #[msg("Overflow when adding the amount to the destination account")]
-    Overflow
+    Overflow,
+    #[msg("Each amount needs a destination wrapped account, its owner and its idendity")]
+    InvalidBatchAccounts,
+    #[msg("Invalid batch destination")]
+    InvalidBatchDestination,
+    #[msg("The memo is too long")]
+    MemoTooLong,
+    #[msg("The destination account requires a memo")]
+    MemoRequired
 }
 
 #[error_code]
@@ -45,6 +65,28 @@ pub enum TwoAuthError {
     WrongApproval,
     #[msg("The Approval has expired")]
     ExpiredApproval,
+    #[msg("The general white list does not belong to the two auth entity")]
+    WrongGeneralWhiteList,
+    #[msg("The two auth rule is invalid")]
+    InvalidRule,
+    #[msg("The price account is needed, the two auth max values are in the reference currency")]
+    MissingPrice,
+    #[msg("The price is too old")]
+    StalePrice,
+    #[msg("There is no two auth policy")]
+    NoTwoAuthPolicy,
+    #[msg("There is no pending two auth update")]
+    NoPendingUpdate,
+    #[msg("The downgrade delay has not passed")]
+    DowngradeDelayNotPassed,
+    #[msg("The price decimals are too large to compute a value")]
+    InvalidPriceDecimals,
+    #[msg("The downgrade delay has passed, only the owner can cancel the update")]
+    DowngradeDelayPassed,
+    #[msg("The downgrade delay can't be negative")]
+    InvalidDowngradeDelay,
+    #[msg("The duration of a two auth function can't be zero")]
+    InvalidDuration,
 }
 
 #[error_code]
@@ -53,4 +95,81 @@ pub enum RecoveryError {
     RecoveryTimeNotPassed,
     #[msg("Not enough signatures")]
     NotEnoughSignatures,
-}
\ No newline at end of file
+}
+#[error_code]
+pub enum AllowanceError {
+    #[msg("The amount exceeds the delegate allowance")]
+    AllowanceExceeded,
+    #[msg("The allowance has expired")]
+    AllowanceExpired,
+}
+
+#[error_code]
+pub enum ScheduledTransferError {
+    #[msg("The scheduled transfer is not due yet")]
+    NotDue,
+    #[msg("The interval and the count must be positive")]
+    InvalidSchedule,
+}
+
+#[error_code]
+pub enum EscrowError {
+    #[msg("The escrow deadline has passed")]
+    DeadlinePassed,
+    #[msg("The escrow deadline has not passed")]
+    DeadlineNotPassed,
+    #[msg("The arbiter has to sign the release")]
+    WrongArbiter,
+    #[msg("The release has to be signed by the sender or by the receiver and the arbiter")]
+    ReleaseNotAuthorized,
+}
+
+#[error_code]
+pub enum TrustAgreementError {
+    #[msg("The first wrapper must be lower than the second one")]
+    InvalidWrapperOrder,
+}
+
+#[error_code]
+pub enum LimitError {
+    #[msg("The wrapper has baseline limits, the limit counter is missing")]
+    MissingLimitCounter,
+    #[msg("The limit counter was created for other limits and has to be reset")]
+    LimitCounterOutdated,
+    #[msg("The limit counter matches the current limits")]
+    LimitCounterUpToDate,
+    #[msg("The transfer exceeds the amount limit of the wrapper")]
+    AmountLimitExceeded,
+    #[msg("The transfer exceeds the transaction limit of the wrapper")]
+    TransactionLimitExceeded,
+    #[msg("The duration of the baseline limits must be positive")]
+    InvalidBaselineLimits,
+}
+
+#[error_code]
+pub enum TravelRuleError {
+    #[msg("The transfer reaches the travel rule threshold, a travel rule record is required")]
+    RecordRequired,
+    #[msg("The travel rule record is bound to another transfer")]
+    RecordMismatch,
+    #[msg("The batch amounts must stay under the travel rule threshold")]
+    BatchAboveThreshold,
+}
+
+#[error_code]
+pub enum BlocklistError {
+    #[msg("The owner is on the blocklist of the wrapper")]
+    OwnerBlocked,
+    #[msg("The blocklist entry does not match the owner")]
+    InvalidBlocklistEntry,
+}
+
+#[error_code]
+pub enum NullifierError {
+    #[msg("The nullifier account is missing or not at the nullifier address")]
+    InvalidNullifierAccount,
+    #[msg("The nullifier is bound to an identity that has not been recovered")]
+    NullifierAlreadyUsed,
+    #[msg("The identity bound to the nullifier has to be passed")]
+    MissingPreviousIdendity,
+}
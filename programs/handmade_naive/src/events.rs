@@ -0,0 +1,406 @@
+use anchor_lang::prelude::*;
+
+use crate::{BaselineLimits, FeeSchedule};
+
+// Wrapper events
+
+#[event]
+pub struct WrapperInitialized {
+    pub wrapper_account: Pubkey,
+    pub approver: Pubkey,
+    pub list_issuer: Vec<Pubkey>,
+    pub time: i64,
+}
+
+#[event]
+pub struct WrapperIssuerAdded {
+    pub wrapper_account: Pubkey,
+    pub issuer: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct WrapperIssuerRemoved {
+    pub wrapper_account: Pubkey,
+    pub issuer: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub previous_len: u64,
+    pub len: u64,
+    pub time: i64,
+}
+
+#[event]
+pub struct TreasuryInitialized {
+    pub treasury: Pubkey,
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub user_supply: u64,
+    pub time: i64,
+}
+
+#[event]
+pub struct WrappedAccountInitialized {
+    pub wrapped_token_account: Pubkey,
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct AllowUnverifiedRecipientsSet {
+    pub wrapper_account: Pubkey,
+    pub allow_unverified_recipients: bool,
+    pub time: i64,
+}
+
+#[event]
+pub struct RequireMemoSet {
+    pub wrapped_token_account: Pubkey,
+    pub require_memo: bool,
+    pub time: i64,
+}
+
+#[event]
+pub struct BlocklistEntryAdded {
+    pub wrapper_account: Pubkey,
+    pub owner: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct BlocklistEntryRemoved {
+    pub wrapper_account: Pubkey,
+    pub owner: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct TravelRuleThresholdSet {
+    pub wrapper_account: Pubkey,
+    pub threshold: Option<u64>,
+    pub time: i64,
+}
+
+#[event]
+pub struct BaselineLimitsSet {
+    pub wrapper_account: Pubkey,
+    pub baseline_limits: Option<BaselineLimits>,
+    pub time: i64,
+}
+
+#[event]
+pub struct LimitCounterInitialized {
+    pub limit_counter: Pubkey,
+    pub wrapped_account: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct LimitCounterReset {
+    pub limit_counter: Pubkey,
+    pub wrapped_account: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct FeeScheduleSet {
+    pub wrapper_account: Pubkey,
+    pub fee_schedule: Option<FeeSchedule>,
+    pub time: i64,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub fee_manager: Pubkey,
+    pub amount: u64,
+    pub time: i64,
+}
+
+#[event]
+pub struct TokensWrapped {
+    pub wrapped_token_account: Pubkey,
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64, // Credited to the wrapped account
+    pub fee: u64,
+    pub time: i64,
+}
+
+#[event]
+pub struct TokensDeposited {
+    pub wrapped_token_account: Pubkey, // Wrapped account of the recipient
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub payer: Pubkey,
+    pub amount: u64, // Credited to the wrapped account
+    pub fee: u64,
+    pub time: i64,
+}
+
+#[event]
+pub struct TokensUnwrapped {
+    pub wrapped_token_account: Pubkey,
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64, // Debited from the wrapped account, the owner receives amount - fee
+    pub fee: u64,
+    pub time: i64,
+}
+
+#[event]
+pub struct WrapFeesSet {
+    pub treasury: Pubkey,
+    pub mint: Pubkey,
+    pub wrap_fee_basis_points: u16,
+    pub unwrap_fee_basis_points: u16,
+    pub time: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub treasury: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub time: i64,
+}
+
+// Idendity events
+
+#[event]
+pub struct IdendityCreated {
+    pub idendity: Pubkey,
+    pub owner: Pubkey,
+    pub issuer: Pubkey,
+    pub expires_at: i64,
+    pub time: i64,
+}
+
+#[event]
+pub struct IdendityIssuerAdded {
+    pub idendity: Pubkey,
+    pub owner: Pubkey,
+    pub issuer: Pubkey,
+    pub expires_at: i64,
+    pub time: i64,
+}
+
+#[event]
+pub struct NullifierRegistered {
+    pub nullifier_account: Pubkey,
+    pub issuer: Pubkey,
+    pub nullifier: [u8; 32],
+    pub idendity: Pubkey,
+    pub previous_idendity: Option<Pubkey>, // Set when the nullifier moves from a recovered identity
+    pub time: i64,
+}
+
+// Transfer events
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Debug)]
+pub enum TwoAuthOutcome {
+    NoPolicy,  // The sender has no two auth policy
+    NotNeeded, // The policy didn't require the two auth
+    Approved,  // Signed by the two auth entity
+}
+
+#[event]
+pub struct TransferEvent {
+    pub source_wrapped_account: Pubkey,
+    pub destination_wrapped_account: Pubkey,
+    pub amount: u64,
+    pub memo: Option<String>, // Payment reference given by the sender
+    pub fee: u64,             // Paid by the sender on top of the amount
+    pub two_auth: TwoAuthOutcome,
+    pub time: i64,
+}
+
+#[event]
+pub struct TransferOutEvent {
+    pub source_wrapped_account: Pubkey,
+    pub destination_token_account: Pubkey, // Any token account of the mint
+    pub amount: u64,                       // Debited from the wrapped account, the destination receives amount - fee
+    pub fee: u64,
+    pub verified_recipient: bool,
+    pub time: i64,
+}
+
+#[event]
+pub struct TravelRuleRecordCreated {
+    pub travel_rule_record: Pubkey,
+    pub source_wrapped_account: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub data_hash: [u8; 32], // Commitment to the originator and beneficiary data
+    pub issuer: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct DelegateApproved {
+    pub allowance: Pubkey,
+    pub wrapped_account: Pubkey,
+    pub delegate: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub time: i64,
+}
+
+#[event]
+pub struct DelegateRevoked {
+    pub allowance: Pubkey,
+    pub wrapped_account: Pubkey,
+    pub delegate: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct ScheduledTransferCreated {
+    pub scheduled_transfer: Pubkey,
+    pub source_wrapped_account: Pubkey,
+    pub destination_wrapped_account: Pubkey,
+    pub amount: u64,
+    pub interval: i64,
+    pub next_execution: i64,
+    pub remaining_count: u32,
+    pub time: i64,
+}
+
+#[event]
+pub struct ScheduledTransferCancelled {
+    pub scheduled_transfer: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct EscrowCreated {
+    pub escrow: Pubkey,
+    pub source_wrapped_account: Pubkey,
+    pub destination_wrapped_account: Pubkey,
+    pub arbiter: Option<Pubkey>,
+    pub amount: u64,
+    pub deadline: i64,
+    pub fee: u64,
+    pub two_auth: TwoAuthOutcome,
+    pub time: i64,
+}
+
+#[event]
+pub struct EscrowReleased {
+    pub escrow: Pubkey,
+    pub destination_wrapped_account: Pubkey,
+    pub amount: u64,
+    pub time: i64,
+}
+
+#[event]
+pub struct EscrowRefunded {
+    pub escrow: Pubkey,
+    pub source_wrapped_account: Pubkey,
+    pub amount: u64,
+    pub time: i64,
+}
+
+#[event]
+pub struct TrustAgreementCreated {
+    pub trust_agreement: Pubkey,
+    pub wrapper_a: Pubkey,
+    pub wrapper_b: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct TrustAgreementRevoked {
+    pub trust_agreement: Pubkey,
+    pub wrapper_account: Pubkey, // Wrapper of the approver ending the agreement
+    pub time: i64,
+}
+
+// Two auth events
+
+#[event]
+pub struct TwoAuthInitialized {
+    pub two_auth: Pubkey,
+    pub owner: Pubkey,
+    pub two_auth_entity: Option<Pubkey>, // None if there is no policy
+    pub time: i64,
+}
+
+#[event]
+pub struct TwoAuthUpdated {
+    pub two_auth: Pubkey,
+    pub owner: Pubkey,
+    pub two_auth_entity: Option<Pubkey>,
+    pub time: i64,
+}
+
+#[event]
+pub struct TwoAuthUpdateScheduled {
+    pub two_auth: Pubkey,
+    pub owner: Pubkey,
+    pub two_auth_entity: Option<Pubkey>,
+    pub effective_at: i64,
+    pub time: i64,
+}
+
+#[event]
+pub struct TwoAuthUpdateCancelled {
+    pub two_auth: Pubkey,
+    pub authority: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct TwoAuthUpdateApplied {
+    pub two_auth: Pubkey,
+    pub owner: Pubkey,
+    pub two_auth_entity: Option<Pubkey>,
+    pub time: i64,
+}
+
+#[event]
+pub struct TwoAuthTightened {
+    pub two_auth: Pubkey,
+    pub owner: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct GeneralWhiteListInitialized {
+    pub general_white_list: Pubkey,
+    pub two_auth_entity: Pubkey,
+    pub white_list: Vec<Pubkey>,
+    pub time: i64,
+}
+
+#[event]
+pub struct GeneralWhiteListAddressAdded {
+    pub general_white_list: Pubkey,
+    pub address: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct GeneralWhiteListAddressRemoved {
+    pub general_white_list: Pubkey,
+    pub address: Pubkey,
+    pub time: i64,
+}
+
+#[event]
+pub struct PriceUpdated {
+    pub price_account: Pubkey,
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub time: i64,
+}
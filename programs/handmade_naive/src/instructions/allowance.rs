@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    check_idendity_not_recovered,
+    events::{DelegateApproved, DelegateRevoked},
+    Allowance, IdAccount, WrappedTokenAccount,
+};
+
+// Approving again the same delegate replaces its allowance, no need to revoke it first
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct ApproveDelegate<'info> {
+    #[account(init_if_needed, seeds=[b"allowance", wrapped_account.key().as_ref(), delegate.as_ref()], bump, payer=owner, space=Allowance::LEN)]
+    pub allowance: Account<'info, Allowance>,
+    #[account(has_one=owner)]
+    pub wrapped_account: Account<'info, WrappedTokenAccount>,
+    #[account(seeds = [b"identity", owner.key().as_ref()], bump)]
+    pub idendity: Account<'info, IdAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegate<'info> {
+    #[account(mut, has_one=wrapped_account, close=owner)]
+    pub allowance: Account<'info, Allowance>,
+    #[account(has_one=owner)]
+    pub wrapped_account: Account<'info, WrappedTokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+pub fn _approve_delegate(ctx: Context<ApproveDelegate>, delegate: Pubkey, amount: u64, expires_at: i64) -> Result<()> {
+    check_idendity_not_recovered(&ctx.accounts.idendity)?;
+
+    let allowance = &mut ctx.accounts.allowance;
+    allowance.wrapped_account = ctx.accounts.wrapped_account.key();
+    allowance.delegate = delegate;
+    allowance.amount = amount;
+    allowance.expires_at = expires_at;
+
+    emit!(DelegateApproved {
+        allowance: allowance.key(),
+        wrapped_account: allowance.wrapped_account,
+        delegate,
+        amount,
+        expires_at,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// The allowance account is closed by anchor, the rent goes back to the owner
+pub fn _revoke_delegate(ctx: Context<RevokeDelegate>) -> Result<()> {
+    let allowance = &ctx.accounts.allowance;
+    emit!(DelegateRevoked {
+        allowance: allowance.key(),
+        wrapped_account: allowance.wrapped_account,
+        delegate: allowance.delegate,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::BlocklistError,
+    events::{BlocklistEntryAdded, BlocklistEntryRemoved},
+    BlocklistEntry, WrapperAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct AddToBlocklist<'info> {
+    #[account(init, seeds=[b"blocklist", wrapper_account.key().as_ref(), owner.as_ref()], bump, payer=payer, space=BlocklistEntry::LEN)]
+    pub blocklist_entry: Account<'info, BlocklistEntry>,
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub approver: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromBlocklist<'info> {
+    #[account(mut, has_one=wrapper_account, close=payer)]
+    pub blocklist_entry: Account<'info, BlocklistEntry>,
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub approver: Signer<'info>,
+    /// CHECK: Receives the rent back
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+pub fn _add_to_blocklist(ctx: Context<AddToBlocklist>, owner: Pubkey) -> Result<()> {
+    let blocklist_entry = &mut ctx.accounts.blocklist_entry;
+    blocklist_entry.wrapper_account = ctx.accounts.wrapper_account.key();
+    blocklist_entry.owner = owner;
+    blocklist_entry.blocked_at = Clock::get()?.unix_timestamp;
+
+    emit!(BlocklistEntryAdded {
+        wrapper_account: blocklist_entry.wrapper_account,
+        owner,
+        time: blocklist_entry.blocked_at,
+    });
+    Ok(())
+}
+
+pub fn _remove_from_blocklist(ctx: Context<RemoveFromBlocklist>) -> Result<()> {
+    emit!(BlocklistEntryRemoved {
+        wrapper_account: ctx.accounts.wrapper_account.key(),
+        owner: ctx.accounts.blocklist_entry.owner,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/*
+    The entry is passed at its PDA address ([b"blocklist", wrapper, owner], checked by the seeds constraint),
+    the owner is blocked as soon as an account exists there
+*/
+#[inline(always)]
+pub fn check_not_blocked(blocklist_entry: &AccountInfo) -> Result<()> {
+    if !blocklist_entry.data_is_empty() {
+        return Err(BlocklistError::OwnerBlocked.into());
+    }
+    Ok(())
+}
+
+// Same check when the address can't be constrained by the accounts struct (remaining accounts)
+pub fn check_not_blocked_at(blocklist_entry: &AccountInfo, wrapper_account: &Pubkey, owner: &Pubkey, program_id: &Pubkey) -> Result<()> {
+    let (address, _) = Pubkey::find_program_address(&[b"blocklist", wrapper_account.as_ref(), owner.as_ref()], program_id);
+    if blocklist_entry.key() != address {
+        return Err(BlocklistError::InvalidBlocklistEntry.into());
+    }
+    check_not_blocked(blocklist_entry)
+}
@@ -0,0 +1,222 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    check_idendities, check_idendity_not_recovered, check_two_auth,
+    error::{EscrowError, TransferError},
+    events::{EscrowCreated, EscrowRefunded, EscrowReleased},
+    blocklist, fees, two_auth, TransferControls, Escrow, GeneralWhiteList, IdAccount, LimitCounter, PriceAccount, TravelRuleRecord, TwoAuth, WrappedTokenAccount, WrapperAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateEscrow<'info> {
+    #[account(init, seeds=[b"escrow", source_wrapped_account.key().as_ref(), id.to_le_bytes().as_ref()], bump, payer=source_owner, space=Escrow::LEN)]
+    pub escrow: Account<'info, Escrow>,
+    #[account(mut, has_one= wrapper_account, constraint= source_wrapped_account.wrapper_account.key() == destination_wrapped_account.wrapper_account.key())]
+    pub source_wrapped_account: Account<'info, WrappedTokenAccount>,
+    #[account(mut, constraint = source_wrapped_account.owner == source_owner.key())]
+    pub source_owner: Signer<'info>,
+    #[account(seeds = [b"identity", source_owner.key().as_ref()], bump)]
+    pub idendity_sender: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the sender is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref(), source_owner.key().as_ref()], bump)]
+    pub two_auth: Account<'info,TwoAuth>,
+    #[account(constraint = destination_wrapped_account.mint.key() == source_wrapped_account.mint.key() && destination_wrapped_account.key() != source_wrapped_account.key())]
+    pub destination_wrapped_account: Account<'info, WrappedTokenAccount>,
+    #[account(seeds = [b"identity", destination_wrapped_account.owner.as_ref()], bump)]
+    pub idendity_receiver: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the receiver is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), destination_wrapped_account.owner.as_ref()], bump)]
+    pub receiver_blocklist_entry: UncheckedAccount<'info>,
+    pub two_auth_signer: Option<Signer<'info>>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub general_white_list: Option<Account<'info, GeneralWhiteList>>,
+    #[account(seeds=[b"price", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref()], bump)]
+    pub price_account: Option<Account<'info, PriceAccount>>,
+    #[account(mut)]
+    pub fee_collector: Option<Account<'info, WrappedTokenAccount>>, // Needed when the transfer has a fee
+    #[account(mut, seeds=[b"limit_counter", source_wrapped_account.key().as_ref()], bump)]
+    pub limit_counter: Option<Account<'info, LimitCounter>>, // Needed when the wrapper has baseline limits
+    #[account(mut, close = source_owner)]
+    pub travel_rule_record: Option<Account<'info, TravelRuleRecord>>, // Needed from the travel rule threshold of the wrapper, closed by the transfer
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseEscrow<'info> {
+    #[account(mut, has_one=sender, has_one=destination_wrapped_account, has_one=wrapper_account, close=sender)]
+    pub escrow: Account<'info, Escrow>,
+    /// CHECK: The sender of the escrow, receives the rent back
+    #[account(mut)]
+    pub sender: UncheckedAccount<'info>,
+    #[account(seeds = [b"identity", sender.key().as_ref()], bump)]
+    pub idendity_sender: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the sender is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), sender.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub destination_wrapped_account: Account<'info, WrappedTokenAccount>,
+    /// CHECK: The owner of the destination account
+    #[account(constraint = destination_wrapped_account.owner == destination_owner.key())]
+    pub destination_owner: UncheckedAccount<'info>,
+    #[account(seeds = [b"identity", destination_owner.key().as_ref()], bump)]
+    pub idendity_receiver: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the receiver is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), destination_owner.key().as_ref()], bump)]
+    pub receiver_blocklist_entry: UncheckedAccount<'info>,
+    pub releaser: Signer<'info>, // The sender, or the receiver along with the arbiter
+    pub arbiter: Option<Signer<'info>>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+}
+
+#[derive(Accounts)]
+pub struct RefundEscrow<'info> {
+    #[account(mut, has_one=sender, has_one=source_wrapped_account, close=sender)]
+    pub escrow: Account<'info, Escrow>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    /// CHECK: Has to be empty, the sender is blocked otherwise
+    #[account(seeds=[b"blocklist", escrow.wrapper_account.as_ref(), sender.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub source_wrapped_account: Account<'info, WrappedTokenAccount>,
+}
+
+/*
+    The amount leaves the source account when locked, so the checks of transfer (identities and two auth) are done here
+*/
+pub fn _create_escrow(ctx: Context<CreateEscrow>, amount: u64, arbiter: Option<Pubkey>, deadline: i64) -> Result<()> {
+    let source = &mut ctx.accounts.source_wrapped_account;
+
+    if amount > source.amount {
+        return Err(TransferError::InsufficientFunds.into());
+    }
+
+    let idendity_sender = &ctx.accounts.idendity_sender;
+    let idendity_receiver = &ctx.accounts.idendity_receiver;
+    check_idendity_not_recovered(idendity_sender)?;
+    check_idendity_not_recovered(idendity_receiver)?;
+    let controls = TransferControls {
+        sender_blocklist_entry: &ctx.accounts.sender_blocklist_entry,
+        receiver_blocklist_entry: &ctx.accounts.receiver_blocklist_entry,
+        limit_counter: ctx.accounts.limit_counter.as_deref_mut(),
+        travel_rule_record: ctx.accounts.travel_rule_record.as_deref(),
+    };
+    controls.check_not_blocked()?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    if deadline <= current_time {
+        return Err(EscrowError::DeadlinePassed.into());
+    }
+
+    let two_auth = &mut ctx.accounts.two_auth.two_auth;
+    let mut two_auth_amount = amount;
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth_amount = two_auth::get_two_auth_amount(amount, two_auth_parameters, ctx.accounts.price_account.as_deref(), current_time)?;
+    }
+    let general_white_list = ctx.accounts.general_white_list.as_deref();
+    let two_auth_outcome = check_two_auth(two_auth, &ctx.accounts.two_auth_signer, two_auth_amount, 1, current_time, idendity_receiver.key(), &idendity_receiver.issuers, general_white_list)?;
+
+    let allowed_issuers = &ctx.accounts.wrapper_account.list_issuer;
+    check_idendities(&idendity_sender.issuers, allowed_issuers, current_time)?;
+    check_idendities(&idendity_receiver.issuers, allowed_issuers, current_time)?;
+
+    controls.check_limits(&ctx.accounts.wrapper_account, &source.key(), &ctx.accounts.destination_wrapped_account.key(), amount, current_time)?;
+    let fee = fees::collect_fee(&ctx.accounts.wrapper_account, source, ctx.accounts.fee_collector.as_mut(), amount)?;
+    source.amount = source.amount.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+    source.last_tx = current_time;
+
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth::commit_two_auth(two_auth_amount, 1, two_auth_parameters, current_time);
+    }
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.wrapper_account = ctx.accounts.wrapper_account.key();
+    escrow.mint = source.mint;
+    escrow.sender = ctx.accounts.source_owner.key();
+    escrow.source_wrapped_account = source.key();
+    escrow.destination_wrapped_account = ctx.accounts.destination_wrapped_account.key();
+    escrow.arbiter = arbiter;
+    escrow.amount = amount;
+    escrow.deadline = deadline;
+
+    emit!(EscrowCreated {
+        escrow: escrow.key(),
+        source_wrapped_account: escrow.source_wrapped_account,
+        destination_wrapped_account: escrow.destination_wrapped_account,
+        arbiter,
+        amount,
+        deadline,
+        fee,
+        two_auth: two_auth_outcome,
+        time: current_time,
+    });
+    Ok(())
+}
+
+/*
+    Signed before the deadline by the sender, or by the receiver and the arbiter
+    The identities may have expired, been recovered or blocked since the lock, they are checked again
+*/
+pub fn _release_escrow(ctx: Context<ReleaseEscrow>) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    escrow.check_release(
+        ctx.accounts.releaser.key(),
+        ctx.accounts.destination_owner.key(),
+        ctx.accounts.arbiter.as_ref().map(|arbiter| arbiter.key()),
+        current_time,
+    )?;
+
+    let idendity_sender = &ctx.accounts.idendity_sender;
+    let idendity_receiver = &ctx.accounts.idendity_receiver;
+    check_idendity_not_recovered(idendity_sender)?;
+    check_idendity_not_recovered(idendity_receiver)?;
+    let controls = TransferControls {
+        sender_blocklist_entry: &ctx.accounts.sender_blocklist_entry,
+        receiver_blocklist_entry: &ctx.accounts.receiver_blocklist_entry,
+        limit_counter: None, // The amount was counted when locked
+        travel_rule_record: None,
+    };
+    controls.check_not_blocked()?;
+
+    let allowed_issuers = &ctx.accounts.wrapper_account.list_issuer;
+    check_idendities(&idendity_sender.issuers, allowed_issuers, current_time)?;
+    check_idendities(&idendity_receiver.issuers, allowed_issuers, current_time)?;
+
+    let destination = &mut ctx.accounts.destination_wrapped_account;
+    destination.amount = destination.amount.checked_add(escrow.amount).ok_or(TransferError::Overflow)?;
+
+    emit!(EscrowReleased {
+        escrow: escrow.key(),
+        destination_wrapped_account: destination.key(),
+        amount: escrow.amount,
+        time: current_time,
+    });
+    Ok(())
+}
+
+/*
+    Only the locked amount goes back to the sender: the fee collected when the escrow was created is kept by the wrapper
+*/
+pub fn _refund_escrow(ctx: Context<RefundEscrow>) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    blocklist::check_not_blocked(&ctx.accounts.sender_blocklist_entry)?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    escrow.check_refund(current_time)?;
+
+    let source = &mut ctx.accounts.source_wrapped_account;
+    source.amount = source.amount.checked_add(escrow.amount).ok_or(TransferError::Overflow)?;
+
+    emit!(EscrowRefunded {
+        escrow: escrow.key(),
+        source_wrapped_account: source.key(),
+        amount: escrow.amount,
+        time: current_time,
+    });
+    Ok(())
+}
@@ -0,0 +1,232 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    error::{TransferError, WrapperError},
+    events::{FeeScheduleSet, FeesWithdrawn, TreasuryWithdrawn, WrapFeesSet},
+    transfer_from_wrapper, FeeSchedule, Treasury, WrappedTokenAccount, WrapperAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(fee_schedule: Option<FeeSchedule>)]
+pub struct SetFeeSchedule<'info> {
+    #[account(mut, seeds=[b"wrapper", approver.key().as_ref()], bump, realloc=wrapper_account.get_set_fee_schedule_len(&fee_schedule), realloc::payer=payer, realloc::zero=false)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub approver: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// The fee collector of a mint is the wrapped account owned by the wrapper itself
+#[derive(Accounts)]
+pub struct InitializeFeeCollector<'info> {
+    #[account(init, seeds=[b"wrapped_token", wrapper_account.key().as_ref(), mint.key().as_ref(), wrapper_account.key().as_ref()], bump, payer=payer, space=WrappedTokenAccount::LEN)]
+    pub fee_collector: Account<'info, WrappedTokenAccount>,
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    /// CHECK: The approver of the wrapper
+    pub approver: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    /// CHECK: The approver of the wrapper
+    pub approver: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"wrapped_token", wrapper_account.key().as_ref(), mint.key().as_ref(), wrapper_account.key().as_ref()], bump)]
+    pub fee_collector: Account<'info, WrappedTokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = wrapper_account,
+    )]
+    pub wrapper_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub fee_manager_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub fee_manager: Signer<'info>,
+    #[account(mut, seeds=[b"treasury", wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetWrapFees<'info> {
+    #[account(mut, has_one=wrapper_account)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub approver: Signer<'info>,
+    #[account(mut, seeds=[b"treasury", wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = wrapper_account,
+    )]
+    pub wrapper_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn _set_fee_schedule(ctx: Context<SetFeeSchedule>, fee_schedule: Option<FeeSchedule>) -> Result<()> {
+    if fee_schedule.as_ref().is_some_and(|f| !f.is_valid()) {
+        return Err(WrapperError::InvalidFeeSchedule.into());
+    }
+
+    let wrapper_account = &mut ctx.accounts.wrapper_account;
+    wrapper_account.fee_schedule = fee_schedule;
+
+    emit!(FeeScheduleSet {
+        wrapper_account: wrapper_account.key(),
+        fee_schedule: wrapper_account.fee_schedule.clone(),
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn _initialize_fee_collector(ctx: Context<InitializeFeeCollector>) -> Result<()> {
+    let fee_collector = &mut ctx.accounts.fee_collector;
+    fee_collector.amount = 0;
+    fee_collector.owner = ctx.accounts.wrapper_account.key();
+    fee_collector.wrapper_account = ctx.accounts.wrapper_account.key();
+    fee_collector.mint = ctx.accounts.mint.key();
+    fee_collector.last_tx = Clock::get()?.unix_timestamp;
+    fee_collector.require_memo = false;
+    Ok(())
+}
+
+/*
+    The fee manager unwraps the collected fees: the tokens leave the wrapper token account
+*/
+pub fn _withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+    let wrapper_account = &ctx.accounts.wrapper_account;
+    let is_fee_manager = wrapper_account.fee_schedule.as_ref().is_some_and(|f| f.fee_manager == ctx.accounts.fee_manager.key());
+    if !is_fee_manager {
+        return Err(WrapperError::NotFeeManager.into());
+    }
+
+    let fee_collector = &mut ctx.accounts.fee_collector;
+    fee_collector.amount = fee_collector.amount.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+    // The fee collector is a wrapped account, its amount is part of the user supply
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.user_supply = treasury.user_supply.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+
+    transfer_from_wrapper(
+        &ctx.accounts.token_program,
+        &ctx.accounts.wrapper_token_account,
+        &ctx.accounts.fee_manager_token_account,
+        wrapper_account,
+        &ctx.accounts.approver.key(),
+        ctx.bumps.wrapper_account,
+        amount,
+    )?;
+    ctx.accounts.wrapper_token_account.reload()?;
+    if !ctx.accounts.treasury.is_backed(ctx.accounts.wrapper_token_account.amount) {
+        return Err(WrapperError::TreasuryNotBacked.into());
+    }
+
+    emit!(FeesWithdrawn {
+        wrapper_account: wrapper_account.key(),
+        mint: ctx.accounts.mint.key(),
+        fee_manager: ctx.accounts.fee_manager.key(),
+        amount,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn _set_wrap_fees(ctx: Context<SetWrapFees>, wrap_fee_basis_points: u16, unwrap_fee_basis_points: u16) -> Result<()> {
+    if wrap_fee_basis_points > 10_000 || unwrap_fee_basis_points > 10_000 {
+        return Err(WrapperError::InvalidFeeSchedule.into());
+    }
+
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.wrap_fee_basis_points = wrap_fee_basis_points;
+    treasury.unwrap_fee_basis_points = unwrap_fee_basis_points;
+
+    emit!(WrapFeesSet {
+        treasury: treasury.key(),
+        mint: treasury.mint,
+        wrap_fee_basis_points,
+        unwrap_fee_basis_points,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/*
+    The approver takes the wrap and unwrap fees out of the wrapper, the user supply stays fully backed
+*/
+pub fn _withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.collected_fees = treasury.collected_fees.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+
+    transfer_from_wrapper(
+        &ctx.accounts.token_program,
+        &ctx.accounts.wrapper_token_account,
+        &ctx.accounts.destination_token_account,
+        &ctx.accounts.wrapper_account,
+        &ctx.accounts.approver.key(),
+        ctx.bumps.wrapper_account,
+        amount,
+    )?;
+    ctx.accounts.wrapper_token_account.reload()?;
+    let treasury = &ctx.accounts.treasury;
+    if !treasury.is_backed(ctx.accounts.wrapper_token_account.amount) {
+        return Err(WrapperError::TreasuryNotBacked.into());
+    }
+
+    emit!(TreasuryWithdrawn {
+        treasury: treasury.key(),
+        mint: treasury.mint,
+        amount,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/*
+    Moves the fee due by the sender of `amount` from the source account to the fee collector of the mint
+    Returns the fee, the fee collector is only needed when there is a fee
+*/
+pub fn collect_fee(
+    wrapper_account: &Account<WrapperAccount>,
+    source: &mut WrappedTokenAccount,
+    fee_collector: Option<&mut Account<WrappedTokenAccount>>,
+    amount: u64,
+) -> Result<u64> {
+    let fee = wrapper_account.get_fee(amount, &source.owner);
+    if fee == 0 {
+        return Ok(0);
+    }
+
+    let fee_collector = fee_collector.ok_or(WrapperError::MissingFeeCollector)?;
+    if fee_collector.wrapper_account != wrapper_account.key()
+        || fee_collector.owner != wrapper_account.key()
+        || fee_collector.mint != source.mint
+    {
+        return Err(WrapperError::WrongFeeCollector.into());
+    }
+
+    source.amount = source.amount.checked_sub(fee).ok_or(TransferError::InsufficientFunds)?;
+    fee_collector.amount = fee_collector.amount.checked_add(fee).ok_or(TransferError::Overflow)?;
+    Ok(fee)
+}
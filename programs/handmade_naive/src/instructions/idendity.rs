@@ -1,8 +1,13 @@
 use anchor_lang::prelude::*;
 
-use crate::{error::IdendityError, IdAccount, Issuer, WrapperAccount};
+use crate::{
+    error::{IdendityError, NullifierError},
+    events::{IdendityCreated, IdendityIssuerAdded, NullifierRegistered},
+    IdAccount, Issuer, NullifierAccount, WrapperAccount,
+};
 
 #[derive(Accounts)]
+#[instruction(id_validity_duration: i64, nullifier: Option<[u8; 32]>)]
 pub struct InitializeId<'info> {
     #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
     pub wrapper_account: Account<'info, WrapperAccount>,
@@ -14,10 +19,16 @@ pub struct InitializeId<'info> {
     pub owner: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
+    // Needed with a nullifier
+    #[account(init_if_needed, seeds = [b"nullifier", issuer.key().as_ref(), nullifier.unwrap_or_default().as_ref()], bump, payer = payer, space = NullifierAccount::LEN)]
+    pub nullifier_account: Option<Account<'info, NullifierAccount>>,
+    // Identity bound to the nullifier, needed to move the nullifier from it
+    pub previous_idendity: Option<Account<'info, IdAccount>>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
+#[instruction(id_validity_duration: i64, nullifier: Option<[u8; 32]>)]
 pub struct AddIssuer<'info> {
     #[account(mut)]
     pub issuer: Signer<'info>,
@@ -29,10 +40,15 @@ pub struct AddIssuer<'info> {
     pub idendity: Account<'info, IdAccount>,
     #[account(mut)]
     pub owner: Signer<'info>,
+    // Needed with a nullifier
+    #[account(init_if_needed, seeds = [b"nullifier", issuer.key().as_ref(), nullifier.unwrap_or_default().as_ref()], bump, payer = owner, space = NullifierAccount::LEN)]
+    pub nullifier_account: Option<Account<'info, NullifierAccount>>,
+    // Identity bound to the nullifier, needed to move the nullifier from it
+    pub previous_idendity: Option<Account<'info, IdAccount>>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn _initialize_id(ctx: Context<InitializeId>, id_validity_duration: i64) -> Result<()> {
+pub fn _initialize_id(ctx: Context<InitializeId>, id_validity_duration: i64, nullifier: Option<[u8; 32]>) -> Result<()> {
     // Check if the issuer has been approved
     let issuer = &ctx.accounts.issuer;
     let wrapper_account = &ctx.accounts.wrapper_account;
@@ -51,11 +67,23 @@ pub fn _initialize_id(ctx: Context<InitializeId>, id_validity_duration: i64) ->
         expires_at: clock.unix_timestamp + id_validity_duration,
         active: true,
     };
+
+    emit!(IdendityCreated {
+        idendity: idendity.key(),
+        owner: idendity.owner,
+        issuer: issuer.key,
+        expires_at: issuer.expires_at,
+        time: clock.unix_timestamp,
+    });
+
     idendity.issuers = vec![issuer];
+
+    let idendity = ctx.accounts.idendity.key();
+    register_nullifier(ctx.accounts.nullifier_account.as_mut(), ctx.accounts.previous_idendity.as_ref(), ctx.accounts.issuer.key(), nullifier, idendity)?;
     Ok(())
 }
 
-pub fn _add_issuer_to_id(ctx: Context<AddIssuer>, id_validity_duration: i64) -> Result<()> {
+pub fn _add_issuer_to_id(ctx: Context<AddIssuer>, id_validity_duration: i64, nullifier: Option<[u8; 32]>) -> Result<()> {
     // Check if the issuer has been approved
     let issuer = &ctx.accounts.issuer;
     let wrapper_account = &ctx.accounts.wrapper_account;
@@ -81,13 +109,59 @@ pub fn _add_issuer_to_id(ctx: Context<AddIssuer>, id_validity_duration: i64) ->
         expires_at: current_timestamp + id_validity_duration,
         active: true,
     };
-    issuers.push(new_issuer);
+    issuers.push(new_issuer.clone());
+
+    emit!(IdendityIssuerAdded {
+        idendity: ctx.accounts.idendity.key(),
+        owner: ctx.accounts.owner.key(),
+        issuer: new_issuer.key,
+        expires_at: new_issuer.expires_at,
+        time: current_timestamp,
+    });
+
+    let idendity = ctx.accounts.idendity.key();
+    register_nullifier(ctx.accounts.nullifier_account.as_mut(), ctx.accounts.previous_idendity.as_ref(), ctx.accounts.issuer.key(), nullifier, idendity)?;
 
     Ok(())
 }
 
+/*
+    Binds the nullifier to the identity for the issuer, the account is created on first use
+*/
+fn register_nullifier(
+    nullifier_account: Option<&mut Account<NullifierAccount>>,
+    previous_idendity: Option<&Account<IdAccount>>,
+    issuer: Pubkey,
+    nullifier: Option<[u8; 32]>,
+    idendity: Pubkey,
+) -> Result<()> {
+    let (nullifier_account, nullifier) = match (nullifier_account, nullifier) {
+        (None, None) => return Ok(()),
+        (Some(nullifier_account), Some(nullifier)) => (nullifier_account, nullifier),
+        _ => return Err(NullifierError::InvalidNullifierAccount.into()),
+    };
+    let previous_idendity = previous_idendity.map(|previous| (previous.key(), &**previous));
+    if !nullifier_account.needs_binding(idendity, previous_idendity)? {
+        return Ok(());
+    }
 
-
+    let time = Clock::get()?.unix_timestamp;
+    let previous = Some(nullifier_account.idendity).filter(|previous| *previous != Pubkey::default());
+    nullifier_account.issuer = issuer;
+    nullifier_account.nullifier = nullifier;
+    nullifier_account.idendity = idendity;
+    nullifier_account.created_at = time;
+
+    emit!(NullifierRegistered {
+        nullifier_account: nullifier_account.key(),
+        issuer,
+        nullifier,
+        idendity,
+        previous_idendity: previous,
+        time,
+    });
+    Ok(())
+}
 
 #[inline(always)]
 pub fn check_idendity_not_recovered(idendity: &IdAccount) -> Result<()> {
@@ -0,0 +1,131 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::LimitError, events::{BaselineLimitsSet, LimitCounterInitialized, LimitCounterReset}, BaselineLimits, LimitCounter, WrappedTokenAccount, WrapperAccount
+};
+
+#[derive(Accounts)]
+#[instruction(baseline_limits: Option<BaselineLimits>)]
+pub struct SetBaselineLimits<'info> {
+    #[account(mut, seeds=[b"wrapper", approver.key().as_ref()], bump, realloc=wrapper_account.get_set_baseline_limits_len(&baseline_limits), realloc::payer=payer, realloc::zero=false)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub approver: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Anyone can pay for the counter of a wrapped account
+#[derive(Accounts)]
+pub struct InitializeLimitCounter<'info> {
+    #[account(
+        init,
+        seeds=[b"limit_counter", wrapped_account.key().as_ref()],
+        bump,
+        payer=payer,
+        space=LimitCounter::get_len(&wrapper_account.baseline_limits.as_ref().ok_or(LimitError::MissingLimitCounter)?.duration)
+    )]
+    pub limit_counter: Account<'info, LimitCounter>,
+    #[account(has_one=wrapper_account)]
+    pub wrapped_account: Account<'info, WrappedTokenAccount>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResetLimitCounter<'info> {
+    #[account(
+        mut,
+        seeds=[b"limit_counter", wrapped_account.key().as_ref()],
+        bump,
+        realloc=LimitCounter::get_len(&wrapper_account.baseline_limits.as_ref().ok_or(LimitError::MissingLimitCounter)?.duration),
+        realloc::payer=payer,
+        realloc::zero=false
+    )]
+    pub limit_counter: Account<'info, LimitCounter>,
+    #[account(has_one=wrapper_account)]
+    pub wrapped_account: Account<'info, WrappedTokenAccount>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn _set_baseline_limits(ctx: Context<SetBaselineLimits>, baseline_limits: Option<BaselineLimits>) -> Result<()> {
+    if baseline_limits.as_ref().is_some_and(|b| !b.is_valid()) {
+        return Err(LimitError::InvalidBaselineLimits.into());
+    }
+
+    let wrapper_account = &mut ctx.accounts.wrapper_account;
+    wrapper_account.baseline_limits = baseline_limits;
+
+    emit!(BaselineLimitsSet {
+        wrapper_account: wrapper_account.key(),
+        baseline_limits: wrapper_account.baseline_limits.clone(),
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn _initialize_limit_counter(ctx: Context<InitializeLimitCounter>) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    // The space constraint already checked that the wrapper has limits
+    let baseline_limits = ctx.accounts.wrapper_account.baseline_limits.as_ref().unwrap();
+
+    let limit_counter = &mut ctx.accounts.limit_counter;
+    limit_counter.set_inner(LimitCounter::new(ctx.accounts.wrapped_account.key(), &baseline_limits.duration, current_time));
+
+    emit!(LimitCounterInitialized {
+        limit_counter: limit_counter.key(),
+        wrapped_account: limit_counter.wrapped_account,
+        time: current_time,
+    });
+    Ok(())
+}
+
+/*
+    Only a counter sized for previous limits can be reset, otherwise the owner could clear its own counters
+*/
+pub fn _reset_limit_counter(ctx: Context<ResetLimitCounter>) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let baseline_limits = ctx.accounts.wrapper_account.baseline_limits.as_ref().unwrap();
+
+    let limit_counter = &mut ctx.accounts.limit_counter;
+    if !limit_counter.is_outdated(baseline_limits) {
+        return Err(LimitError::LimitCounterUpToDate.into());
+    }
+    limit_counter.set_inner(LimitCounter::new(ctx.accounts.wrapped_account.key(), &baseline_limits.duration, current_time));
+
+    emit!(LimitCounterReset {
+        limit_counter: limit_counter.key(),
+        wrapped_account: limit_counter.wrapped_account,
+        time: current_time,
+    });
+    Ok(())
+}
+
+/*
+    Checks the outgoing amount and transactions against the baseline limits of the wrapper and counts them,
+    nothing to do when the wrapper has no limits
+*/
+pub fn check_baseline_limits(wrapper_account: &WrapperAccount, limit_counter: Option<&mut LimitCounter>, amount: u64, transactions: u64, time: i64) -> Result<()> {
+    let Some(baseline_limits) = &wrapper_account.baseline_limits else {
+        return Ok(());
+    };
+    let limit_counter = limit_counter.ok_or(LimitError::MissingLimitCounter)?;
+
+    if limit_counter.is_outdated(baseline_limits) {
+        return Err(LimitError::LimitCounterOutdated.into());
+    }
+    if amount > limit_counter.get_remaining_amount(baseline_limits, time) {
+        return Err(LimitError::AmountLimitExceeded.into());
+    }
+    if transactions > limit_counter.get_remaining_transactions(baseline_limits, time) {
+        return Err(LimitError::TransactionLimitExceeded.into());
+    }
+
+    limit_counter.add(amount, transactions, time);
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    events::{AccountMigrated, TreasuryInitialized},
+    Treasury, WrappedTokenAccount, WrapperAccount,
+};
+
+// Longer than the fields appended since the first deployment, they are read as zeros (None, false)
+const MIGRATION_PADDING: usize = 16;
+
+// The wrapper account may predate the fields appended to WrapperAccount, it can't be loaded as Account before being migrated
+#[derive(Accounts)]
+pub struct MigrateWrapper<'info> {
+    /// CHECK: Deserialized by the handler once padded
+    #[account(mut, seeds=[b"wrapper", approver.key().as_ref()], bump, owner=crate::ID)]
+    pub wrapper_account: UncheckedAccount<'info>,
+    pub approver: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless: the migration only appends the missing fields with their default value
+#[derive(Accounts)]
+pub struct MigrateWrappedAccount<'info> {
+    /// CHECK: Deserialized by the handler once padded
+    #[account(mut, owner=crate::ID)]
+    pub wrapped_token_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Mints initialized before the treasury was added: the tokens already held by the wrapper are owed to the users
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub approver: Signer<'info>,
+    // The associated token account created by wrap_token_holder: the user supply is read from it
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = wrapper_account,
+        associated_token::token_program = token_program,
+    )]
+    pub wrapper_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(init, seeds=[b"treasury", wrapper_account.key().as_ref(), mint.key().as_ref()], bump, payer=payer, space=Treasury::LEN)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn _migrate_wrapper(ctx: Context<MigrateWrapper>) -> Result<()> {
+    let wrapper_account = ctx.accounts.wrapper_account.to_account_info();
+    let wrapper: WrapperAccount = deserialize_padded(&wrapper_account.try_borrow_data()?)?;
+    realloc_account(&wrapper_account, &ctx.accounts.payer, &ctx.accounts.system_program, wrapper.get_len())
+}
+
+pub fn _migrate_wrapped_account(ctx: Context<MigrateWrappedAccount>) -> Result<()> {
+    let wrapped_token_account = ctx.accounts.wrapped_token_account.to_account_info();
+    deserialize_padded::<WrappedTokenAccount>(&wrapped_token_account.try_borrow_data()?)?;
+    realloc_account(&wrapped_token_account, &ctx.accounts.payer, &ctx.accounts.system_program, WrappedTokenAccount::LEN)
+}
+
+pub fn _initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.wrapper_account = ctx.accounts.wrapper_account.key();
+    treasury.mint = ctx.accounts.mint.key();
+    treasury.wrap_fee_basis_points = 0;
+    treasury.unwrap_fee_basis_points = 0;
+    treasury.user_supply = ctx.accounts.wrapper_token_account.amount;
+    treasury.collected_fees = 0;
+
+    emit!(TreasuryInitialized {
+        treasury: treasury.key(),
+        wrapper_account: treasury.wrapper_account,
+        mint: treasury.mint,
+        user_supply: treasury.user_supply,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/*
+    Deserializes an account written with an older layout: the data is padded with zeros, so the fields appended since then read as their default value
+    The discriminator is still checked
+*/
+fn deserialize_padded<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    let mut padded = data.to_vec();
+    padded.resize(data.len() + MIGRATION_PADDING, 0);
+    T::try_deserialize(&mut padded.as_slice())
+}
+
+// Grows the account to `len` with zeros, the payer tops up the rent. Nothing to do on an account already migrated
+fn realloc_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    len: usize,
+) -> Result<()> {
+    let current_len = account.data_len();
+    if len <= current_len {
+        return Ok(());
+    }
+
+    let rent = Rent::get()?.minimum_balance(len);
+    if rent > account.lamports() {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            rent - account.lamports(),
+        )?;
+    }
+    account.realloc(len, true)?;
+
+    emit!(AccountMigrated {
+        account: account.key(),
+        previous_len: current_len as u64,
+        len: len as u64,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    #[test]
+    fn wrapper_account_first_layout() {
+        // Layout of the first deployment: approver and list_issuer only
+        let approver = Pubkey::new_unique();
+        let issuer = Pubkey::new_unique();
+        let mut data = WrapperAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(approver.as_ref());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(issuer.as_ref());
+
+        assert!(WrapperAccount::try_deserialize(&mut data.as_slice()).is_err());
+        let wrapper: WrapperAccount = deserialize_padded(&data).unwrap();
+        assert_eq!(wrapper.approver, approver);
+        assert_eq!(wrapper.list_issuer, vec![issuer]);
+        assert!(wrapper.fee_schedule.is_none());
+        assert!(!wrapper.allow_unverified_recipients);
+        assert!(wrapper.baseline_limits.is_none());
+        assert!(wrapper.travel_rule_threshold.is_none());
+        assert_eq!(wrapper.get_len(), WrapperAccount::get_init_len(vec![issuer]));
+    }
+
+    #[test]
+    fn wrapped_account_first_layout() {
+        // Layout of the first deployment, without require_memo
+        let mut data = WrappedTokenAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&[1; 32 + 32 + 32]);
+        data.extend_from_slice(&10u64.to_le_bytes());
+        data.extend_from_slice(&5i64.to_le_bytes());
+        assert_eq!(data.len(), WrappedTokenAccount::LEN - 1);
+
+        let wrapped: WrappedTokenAccount = deserialize_padded(&data).unwrap();
+        assert_eq!(wrapped.amount, 10);
+        assert_eq!(wrapped.last_tx, 5);
+        assert!(!wrapped.require_memo);
+    }
+
+    #[test]
+    fn wrong_discriminator() {
+        let data = [0u8; WrappedTokenAccount::LEN];
+        assert!(deserialize_padded::<WrappedTokenAccount>(&data).is_err());
+    }
+}
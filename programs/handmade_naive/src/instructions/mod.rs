@@ -10,5 +10,38 @@ pub use idendity::*;
 pub mod two_auth;
 pub use two_auth::*;
 
+pub mod white_list;
+pub use white_list::*;
+
+pub mod price;
+pub use price::*;
+
+pub mod allowance;
+pub use allowance::*;
+
+pub mod scheduled_transfer;
+pub use scheduled_transfer::*;
+
+pub mod escrow;
+pub use escrow::*;
+
+pub mod fees;
+pub use fees::*;
+
+pub mod trust_agreement;
+pub use trust_agreement::*;
+
+pub mod limits;
+pub use limits::*;
+
+pub mod travel_rule;
+pub use travel_rule::*;
+
+pub mod blocklist;
+pub use blocklist::*;
+
+pub mod migration;
+pub use migration::*;
+
 // pub mod recovery;
-// pub use recovery::*;
\ No newline at end of file
+// pub use recovery::*;
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{events::PriceUpdated, PriceAccount, WrapperAccount};
+
+#[derive(Accounts)]
+pub struct InitializePrice<'info> {
+    #[account(init, seeds=[b"price", wrapper_account.key().as_ref(), mint.key().as_ref()], bump, payer=payer, space=PriceAccount::LEN)]
+    pub price_account: Account<'info, PriceAccount>,
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub approver: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePrice<'info> {
+    #[account(mut, has_one=price_authority)]
+    pub price_account: Account<'info, PriceAccount>,
+    pub price_authority: Signer<'info>,
+}
+
+pub fn _initialize_price(ctx: Context<InitializePrice>, price_authority: Pubkey, price: u64) -> Result<()> {
+    let price_account = &mut ctx.accounts.price_account;
+    price_account.wrapper_account = ctx.accounts.wrapper_account.key();
+    price_account.mint = ctx.accounts.mint.key();
+    price_account.price_authority = price_authority;
+    price_account.price = price;
+    price_account.decimals = ctx.accounts.mint.decimals;
+    price_account.last_update = Clock::get()?.unix_timestamp;
+
+    emit_price_updated(price_account);
+    Ok(())
+}
+
+pub fn _update_price(ctx: Context<UpdatePrice>, price: u64) -> Result<()> {
+    let price_account = &mut ctx.accounts.price_account;
+    price_account.price = price;
+    price_account.last_update = Clock::get()?.unix_timestamp;
+
+    emit_price_updated(price_account);
+    Ok(())
+}
+
+fn emit_price_updated(price_account: &Account<PriceAccount>) {
+    emit!(PriceUpdated {
+        price_account: price_account.key(),
+        wrapper_account: price_account.wrapper_account,
+        mint: price_account.mint,
+        price: price_account.price,
+        time: price_account.last_update,
+    });
+}
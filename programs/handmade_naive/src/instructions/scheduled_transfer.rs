@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    check_idendity_not_recovered, check_memo,
+    error::ScheduledTransferError,
+    events::{ScheduledTransferCancelled, ScheduledTransferCreated},
+    process_transfer, GeneralWhiteList, IdAccount, LimitCounter, PriceAccount, ScheduledTransfer, TravelRuleRecord, TwoAuth, WrappedTokenAccount, WrapperAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64, amount: u64, interval: i64, first_execution: i64, count: u32, memo: Option<String>)]
+pub struct CreateScheduledTransfer<'info> {
+    #[account(init, seeds=[b"scheduled_transfer", source_wrapped_account.key().as_ref(), id.to_le_bytes().as_ref()], bump, payer=owner, space=ScheduledTransfer::get_init_len(&memo))]
+    pub scheduled_transfer: Account<'info, ScheduledTransfer>,
+    #[account(has_one=owner, constraint= source_wrapped_account.wrapper_account.key() == destination_wrapped_account.wrapper_account.key())]
+    pub source_wrapped_account: Account<'info, WrappedTokenAccount>,
+    #[account(constraint = destination_wrapped_account.mint.key() == source_wrapped_account.mint.key())]
+    pub destination_wrapped_account: Account<'info, WrappedTokenAccount>,
+    #[account(seeds = [b"identity", owner.key().as_ref()], bump)]
+    pub idendity: Account<'info, IdAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelScheduledTransfer<'info> {
+    #[account(mut, has_one=owner, close=owner)]
+    pub scheduled_transfer: Account<'info, ScheduledTransfer>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+// Permissionless: the owner authorized the transfer when scheduling it
+#[derive(Accounts)]
+pub struct ExecuteScheduledTransfer<'info> {
+    #[account(mut, has_one=source_wrapped_account, has_one=destination_wrapped_account, constraint = scheduled_transfer.owner == source_owner.key())]
+    pub scheduled_transfer: Account<'info, ScheduledTransfer>,
+    #[account(mut, has_one= wrapper_account, constraint= source_wrapped_account.wrapper_account.key() == destination_wrapped_account.wrapper_account.key())]
+    pub source_wrapped_account: Account<'info, WrappedTokenAccount>,
+    /// CHECK: The owner of the source account, receives the rent back after the last execution
+    #[account(mut, constraint = source_wrapped_account.owner == source_owner.key())]
+    pub source_owner: UncheckedAccount<'info>,
+    #[account(seeds = [b"identity", source_owner.key().as_ref()], bump)]
+    pub idendity_sender: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the sender is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref(), source_owner.key().as_ref()], bump)]
+    pub two_auth: Account<'info,TwoAuth>,
+    #[account(mut, constraint = destination_wrapped_account.mint.key() == source_wrapped_account.mint.key())]
+    pub destination_wrapped_account: Account<'info, WrappedTokenAccount>,
+    /// CHECK: The owner of the destination account
+    #[account(constraint = destination_wrapped_account.owner == destination_owner.key())]
+    pub destination_owner: AccountInfo<'info>,
+    #[account(seeds = [b"identity", destination_owner.key().as_ref()], bump)]
+    pub idendity_receiver: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the receiver is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), destination_owner.key().as_ref()], bump)]
+    pub receiver_blocklist_entry: UncheckedAccount<'info>,
+    pub two_auth_signer: Option<Signer<'info>>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub general_white_list: Option<Account<'info, GeneralWhiteList>>,
+    #[account(seeds=[b"price", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref()], bump)]
+    pub price_account: Option<Account<'info, PriceAccount>>,
+    #[account(mut)]
+    pub fee_collector: Option<Account<'info, WrappedTokenAccount>>, // Needed when the transfer has a fee
+    #[account(mut, seeds=[b"limit_counter", source_wrapped_account.key().as_ref()], bump)]
+    pub limit_counter: Option<Account<'info, LimitCounter>>, // Needed when the wrapper has baseline limits
+    #[account(mut, close = source_owner)]
+    pub travel_rule_record: Option<Account<'info, TravelRuleRecord>>, // Needed from the travel rule threshold of the wrapper, closed by the transfer
+}
+
+pub fn _create_scheduled_transfer(
+    ctx: Context<CreateScheduledTransfer>,
+    amount: u64,
+    interval: i64,
+    first_execution: i64,
+    count: u32,
+    memo: Option<String>,
+) -> Result<()> {
+    check_idendity_not_recovered(&ctx.accounts.idendity)?;
+    check_memo(&memo, ctx.accounts.destination_wrapped_account.require_memo)?;
+    if interval <= 0 || count == 0 {
+        return Err(ScheduledTransferError::InvalidSchedule.into());
+    }
+
+    let scheduled_transfer = &mut ctx.accounts.scheduled_transfer;
+    scheduled_transfer.source_wrapped_account = ctx.accounts.source_wrapped_account.key();
+    scheduled_transfer.destination_wrapped_account = ctx.accounts.destination_wrapped_account.key();
+    scheduled_transfer.owner = ctx.accounts.owner.key();
+    scheduled_transfer.amount = amount;
+    scheduled_transfer.interval = interval;
+    scheduled_transfer.next_execution = first_execution;
+    scheduled_transfer.remaining_count = count;
+    scheduled_transfer.memo = memo;
+
+    emit!(ScheduledTransferCreated {
+        scheduled_transfer: scheduled_transfer.key(),
+        source_wrapped_account: scheduled_transfer.source_wrapped_account,
+        destination_wrapped_account: scheduled_transfer.destination_wrapped_account,
+        amount,
+        interval,
+        next_execution: first_execution,
+        remaining_count: count,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// The scheduled transfer account is closed by anchor, the rent goes back to the owner
+pub fn _cancel_scheduled_transfer(ctx: Context<CancelScheduledTransfer>) -> Result<()> {
+    emit!(ScheduledTransferCancelled {
+        scheduled_transfer: ctx.accounts.scheduled_transfer.key(),
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/*
+    Same checks as transfer (identities, two auth...), evaluated at execution time
+    Without the two auth entity signature, an execution needing the two auth fails and can be retried later
+    A late execution does not catch up the missed ones, see ScheduledTransfer::consume_execution
+*/
+pub fn _execute_scheduled_transfer(ctx: Context<ExecuteScheduledTransfer>) -> Result<()> {
+    let scheduled_transfer = &mut ctx.accounts.scheduled_transfer;
+    let completed = scheduled_transfer.consume_execution(Clock::get()?.unix_timestamp)?;
+
+    let amount = scheduled_transfer.amount;
+    let memo = scheduled_transfer.memo.clone();
+
+    process_transfer(ctx.accounts.transfer_accounts(), amount, memo)?;
+
+    if completed {
+        let source_owner = ctx.accounts.source_owner.to_account_info();
+        ctx.accounts.scheduled_transfer.close(source_owner)?;
+    }
+    Ok(())
+}
@@ -1,18 +1,21 @@
 use anchor_lang::prelude::*;
 
 use crate::{
-    check_idendity_not_recovered, error::{IdendityError, TransferError, TwoAuthError}, two_auth, IdAccount, Issuer, TwoAuth, TwoAuthParameters, WrappedTokenAccount, WrapperAccount
+    check_idendity_not_recovered, events::{TransferEvent, TwoAuthOutcome, WrappedAccountInitialized}, error::{AllowanceError, IdendityError, TransferError, TravelRuleError, TwoAuthError}, blocklist, fees, limits, travel_rule, two_auth, Allowance, ExecuteScheduledTransfer, GeneralWhiteList, IdAccount, Issuer, LimitCounter, PriceAccount, TravelRuleRecord, TwoAuth, TwoAuthParameters, WrappedTokenAccount, WrapperAccount, MAX_MEMO_LEN
 };
 
 #[derive(Accounts)]
 pub struct Transfer<'info> {
     #[account(mut, has_one= wrapper_account, constraint= source_wrapped_account.wrapper_account.key() == destination_wrapped_account.wrapper_account.key())]
     pub source_wrapped_account: Account<'info, WrappedTokenAccount>,
-    #[account(constraint = source_wrapped_account.owner == source_owner.key())]
+    #[account(mut, constraint = source_wrapped_account.owner == source_owner.key())]
     pub source_owner: Signer<'info>,
     #[account(seeds = [b"identity", source_owner.key().as_ref()], bump)]
     pub idendity_sender: Account<'info, IdAccount>,
-    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), source_owner.key().as_ref()], bump)]
+    /// CHECK: Has to be empty, the sender is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref(), source_owner.key().as_ref()], bump)]
     pub two_auth: Account<'info,TwoAuth>,
     #[account(mut, constraint = destination_wrapped_account.mint.key() == source_wrapped_account.mint.key())]
     pub destination_wrapped_account: Account<'info, WrappedTokenAccount>,
@@ -21,35 +24,270 @@ pub struct Transfer<'info> {
     pub destination_owner: AccountInfo<'info>,
     #[account(seeds = [b"identity", destination_owner.key().as_ref()], bump)]
     pub idendity_receiver: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the receiver is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), destination_owner.key().as_ref()], bump)]
+    pub receiver_blocklist_entry: UncheckedAccount<'info>,
     pub two_auth_signer: Option<Signer<'info>>,
     pub wrapper_account: Account<'info, WrapperAccount>,
+    pub general_white_list: Option<Account<'info, GeneralWhiteList>>,
+    #[account(seeds=[b"price", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref()], bump)]
+    pub price_account: Option<Account<'info, PriceAccount>>,
+    #[account(mut)]
+    pub fee_collector: Option<Account<'info, WrappedTokenAccount>>, // Needed when the transfer has a fee
+    #[account(mut, seeds=[b"limit_counter", source_wrapped_account.key().as_ref()], bump)]
+    pub limit_counter: Option<Account<'info, LimitCounter>>, // Needed when the wrapper has baseline limits
+    #[account(mut, close = source_owner)]
+    pub travel_rule_record: Option<Account<'info, TravelRuleRecord>>, // Needed from the travel rule threshold of the wrapper, closed by the transfer
 }
 
-pub fn _transfer(ctx: Context<Transfer>, amount: u64) -> Result<()> {
-    let source = &mut ctx.accounts.source_wrapped_account;
+#[derive(Accounts)]
+pub struct TransferFrom<'info> {
+    #[account(mut, seeds=[b"allowance", source_wrapped_account.key().as_ref(), delegate.key().as_ref()], bump)]
+    pub allowance: Account<'info, Allowance>,
+    pub delegate: Signer<'info>,
+    #[account(mut, has_one= wrapper_account, constraint= source_wrapped_account.wrapper_account.key() == destination_wrapped_account.wrapper_account.key())]
+    pub source_wrapped_account: Account<'info, WrappedTokenAccount>,
+    /// CHECK: The owner of the source account, the delegate signs instead
+    #[account(mut, constraint = source_wrapped_account.owner == source_owner.key())]
+    pub source_owner: UncheckedAccount<'info>,
+    #[account(seeds = [b"identity", source_owner.key().as_ref()], bump)]
+    pub idendity_sender: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the sender is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref(), source_owner.key().as_ref()], bump)]
+    pub two_auth: Account<'info,TwoAuth>,
+    #[account(mut, constraint = destination_wrapped_account.mint.key() == source_wrapped_account.mint.key())]
+    pub destination_wrapped_account: Account<'info, WrappedTokenAccount>,
+    /// CHECK: The owner of the destination account
+    #[account(constraint = destination_wrapped_account.owner == destination_owner.key())]
+    pub destination_owner: AccountInfo<'info>,
+    #[account(seeds = [b"identity", destination_owner.key().as_ref()], bump)]
+    pub idendity_receiver: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the receiver is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), destination_owner.key().as_ref()], bump)]
+    pub receiver_blocklist_entry: UncheckedAccount<'info>,
+    pub two_auth_signer: Option<Signer<'info>>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub general_white_list: Option<Account<'info, GeneralWhiteList>>,
+    #[account(seeds=[b"price", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref()], bump)]
+    pub price_account: Option<Account<'info, PriceAccount>>,
+    #[account(mut)]
+    pub fee_collector: Option<Account<'info, WrappedTokenAccount>>, // Needed when the transfer has a fee
+    #[account(mut, seeds=[b"limit_counter", source_wrapped_account.key().as_ref()], bump)]
+    pub limit_counter: Option<Account<'info, LimitCounter>>, // Needed when the wrapper has baseline limits
+    #[account(mut, close = source_owner)]
+    pub travel_rule_record: Option<Account<'info, TravelRuleRecord>>, // Needed from the travel rule threshold of the wrapper, closed by the transfer
+}
+
+// The sender pays the rent of the destination wrapped account, which must not exist yet
+#[derive(Accounts)]
+pub struct TransferAndCreate<'info> {
+    #[account(mut, has_one= wrapper_account)]
+    pub source_wrapped_account: Account<'info, WrappedTokenAccount>,
+    #[account(mut, constraint = source_wrapped_account.owner == source_owner.key())]
+    pub source_owner: Signer<'info>,
+    #[account(seeds = [b"identity", source_owner.key().as_ref()], bump)]
+    pub idendity_sender: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the sender is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref(), source_owner.key().as_ref()], bump)]
+    pub two_auth: Account<'info,TwoAuth>,
+    #[account(init, seeds=[b"wrapped_token", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref(), destination_owner.key().as_ref()], bump, payer=source_owner, space=WrappedTokenAccount::LEN)]
+    pub destination_wrapped_account: Account<'info, WrappedTokenAccount>,
+    /// CHECK: The owner of the destination account
+    pub destination_owner: AccountInfo<'info>,
+    #[account(seeds = [b"identity", destination_owner.key().as_ref()], bump)]
+    pub idendity_receiver: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the receiver is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), destination_owner.key().as_ref()], bump)]
+    pub receiver_blocklist_entry: UncheckedAccount<'info>,
+    pub two_auth_signer: Option<Signer<'info>>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub general_white_list: Option<Account<'info, GeneralWhiteList>>,
+    #[account(seeds=[b"price", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref()], bump)]
+    pub price_account: Option<Account<'info, PriceAccount>>,
+    #[account(mut)]
+    pub fee_collector: Option<Account<'info, WrappedTokenAccount>>, // Needed when the transfer has a fee
+    #[account(mut, seeds=[b"limit_counter", source_wrapped_account.key().as_ref()], bump)]
+    pub limit_counter: Option<Account<'info, LimitCounter>>, // Needed when the wrapper has baseline limits
+    #[account(mut, close = source_owner)]
+    pub travel_rule_record: Option<Account<'info, TravelRuleRecord>>, // Needed from the travel rule threshold of the wrapper, closed by the transfer
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts used to move funds between two wrapped accounts, whoever signs the transfer (the owner or a delegate)
+pub struct TransferAccounts<'a, 'info> {
+    pub source_wrapped_account: &'a mut Account<'info, WrappedTokenAccount>,
+    pub idendity_sender: &'a IdAccount,
+    pub two_auth: &'a mut Option<TwoAuthParameters>,
+    pub destination_wrapped_account: &'a mut Account<'info, WrappedTokenAccount>,
+    pub idendity_receiver: &'a Account<'info, IdAccount>,
+    pub two_auth_signer: &'a Option<Signer<'info>>,
+    pub wrapper_account: &'a Account<'info, WrapperAccount>,
+    pub general_white_list: Option<&'a GeneralWhiteList>,
+    pub price_account: Option<&'a PriceAccount>,
+    pub fee_collector: Option<&'a mut Account<'info, WrappedTokenAccount>>,
+    pub controls: TransferControls<'a, 'info>,
+}
+
+// Controls of the wrapper on the funds leaving a wrapped account: blocklists of both owners, baseline limits and travel rule
+pub struct TransferControls<'a, 'info> {
+    pub sender_blocklist_entry: &'a AccountInfo<'info>,
+    pub receiver_blocklist_entry: &'a AccountInfo<'info>,
+    pub limit_counter: Option<&'a mut LimitCounter>,
+    pub travel_rule_record: Option<&'a TravelRuleRecord>,
+}
+
+impl<'a, 'info> TransferControls<'a, 'info> {
+    pub fn check_not_blocked(&self) -> Result<()> {
+        blocklist::check_not_blocked(self.sender_blocklist_entry)?;
+        blocklist::check_not_blocked(self.receiver_blocklist_entry)
+    }
+
+    // Counts the transfer in the limit counter, only once the other checks passed
+    pub fn check_limits(self, wrapper_account: &WrapperAccount, source: &Pubkey, destination: &Pubkey, amount: u64, time: i64) -> Result<()> {
+        limits::check_baseline_limits(wrapper_account, self.limit_counter, amount, 1, time)?;
+        travel_rule::check_travel_rule(wrapper_account, self.travel_rule_record, source, destination, amount)
+    }
+}
+
+// The accounts structs processed by process_transfer have the same fields
+macro_rules! impl_transfer_accounts {
+    ($($accounts:ident),*) => {$(
+        impl<'info> $accounts<'info> {
+            pub fn transfer_accounts(&mut self) -> TransferAccounts<'_, 'info> {
+                TransferAccounts {
+                    source_wrapped_account: &mut self.source_wrapped_account,
+                    idendity_sender: &self.idendity_sender,
+                    two_auth: &mut self.two_auth.two_auth,
+                    destination_wrapped_account: &mut self.destination_wrapped_account,
+                    idendity_receiver: &self.idendity_receiver,
+                    two_auth_signer: &self.two_auth_signer,
+                    wrapper_account: &self.wrapper_account,
+                    general_white_list: self.general_white_list.as_deref(),
+                    price_account: self.price_account.as_deref(),
+                    fee_collector: self.fee_collector.as_mut(),
+                    controls: TransferControls {
+                        sender_blocklist_entry: &self.sender_blocklist_entry,
+                        receiver_blocklist_entry: &self.receiver_blocklist_entry,
+                        limit_counter: self.limit_counter.as_deref_mut(),
+                        travel_rule_record: self.travel_rule_record.as_deref(),
+                    },
+                }
+            }
+        }
+    )*};
+}
+
+impl_transfer_accounts!(Transfer, TransferFrom, TransferAndCreate, ExecuteScheduledTransfer);
+
+// The destinations are passed as remaining accounts: (destination_wrapped_account, destination_owner, idendity_receiver, receiver_blocklist_entry) for each amount
+#[derive(Accounts)]
+pub struct BatchTransfer<'info> {
+    #[account(mut, has_one= wrapper_account)]
+    pub source_wrapped_account: Account<'info, WrappedTokenAccount>,
+    #[account(constraint = source_wrapped_account.owner == source_owner.key())]
+    pub source_owner: Signer<'info>,
+    #[account(seeds = [b"identity", source_owner.key().as_ref()], bump)]
+    pub idendity_sender: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the sender is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref(), source_owner.key().as_ref()], bump)]
+    pub two_auth: Account<'info,TwoAuth>,
+    pub two_auth_signer: Option<Signer<'info>>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub general_white_list: Option<Account<'info, GeneralWhiteList>>,
+    #[account(seeds=[b"price", wrapper_account.key().as_ref(), source_wrapped_account.mint.as_ref()], bump)]
+    pub price_account: Option<Account<'info, PriceAccount>>,
+    #[account(mut)]
+    pub fee_collector: Option<Account<'info, WrappedTokenAccount>>, // Needed when the transfer has a fee
+    #[account(mut, seeds=[b"limit_counter", source_wrapped_account.key().as_ref()], bump)]
+    pub limit_counter: Option<Account<'info, LimitCounter>>, // Needed when the wrapper has baseline limits
+}
+
+pub fn _transfer(ctx: Context<Transfer>, amount: u64, memo: Option<String>) -> Result<()> {
+    process_transfer(ctx.accounts.transfer_accounts(), amount, memo)
+}
+
+/*
+    A delegate moves funds within its allowance, the owner's identity and two auth are checked as for transfer
+    The fee paid by the owner is spent from the allowance along with the amount
+*/
+pub fn _transfer_from(ctx: Context<TransferFrom>, amount: u64, memo: Option<String>) -> Result<()> {
+    let allowance = &mut ctx.accounts.allowance;
+
+    if Clock::get()?.unix_timestamp >= allowance.expires_at {
+        return Err(AllowanceError::AllowanceExpired.into());
+    }
+    let fee = ctx.accounts.wrapper_account.get_fee(amount, &ctx.accounts.source_wrapped_account.owner);
+    let spent = amount.checked_add(fee).ok_or(AllowanceError::AllowanceExceeded)?;
+    allowance.amount = allowance.amount.checked_sub(spent).ok_or(AllowanceError::AllowanceExceeded)?;
+
+    process_transfer(ctx.accounts.transfer_accounts(), amount, memo)
+}
+
+/*
+    First payment to a receiver without wrapped account for the mint: the account is created then the transfer is processed as usual,
+    the identity of the receiver is checked by process_transfer
+*/
+pub fn _transfer_and_create(ctx: Context<TransferAndCreate>, amount: u64, memo: Option<String>) -> Result<()> {
     let destination = &mut ctx.accounts.destination_wrapped_account;
+    destination.amount = 0;
+    destination.owner = ctx.accounts.destination_owner.key();
+    destination.wrapper_account = ctx.accounts.wrapper_account.key();
+    destination.mint = ctx.accounts.source_wrapped_account.mint;
+    destination.last_tx = Clock::get()?.unix_timestamp;
+    destination.require_memo = false;
+
+    emit!(WrappedAccountInitialized {
+        wrapped_token_account: destination.key(),
+        wrapper_account: destination.wrapper_account,
+        mint: destination.mint,
+        owner: destination.owner,
+        time: destination.last_tx,
+    });
+
+    process_transfer(ctx.accounts.transfer_accounts(), amount, memo)
+}
+
+pub fn process_transfer(accounts: TransferAccounts, amount: u64, memo: Option<String>) -> Result<()> {
+    let source = accounts.source_wrapped_account;
+    let destination = accounts.destination_wrapped_account;
 
     let self_transfer = source.key() == destination.key();
 
     if amount > source.amount {
         return Err(TransferError::InsufficientFunds.into());
     }
+    check_memo(&memo, destination.require_memo && !self_transfer)?;
 
-    check_idendity_not_recovered(&ctx.accounts.idendity_sender)?;
+    check_idendity_not_recovered(accounts.idendity_sender)?;
+    accounts.controls.check_not_blocked()?;
     if !self_transfer{
-        check_idendity_not_recovered(&ctx.accounts.idendity_receiver)?;
+        check_idendity_not_recovered(accounts.idendity_receiver)?;
     }
-    let two_auth = &mut ctx.accounts.two_auth.two_auth;
-    let two_auth_signer = &ctx.accounts.two_auth_signer;
+    let two_auth = accounts.two_auth;
+    let two_auth_signer = accounts.two_auth_signer;
 
     let current_time = Clock::get()?.unix_timestamp;
 
+    // The amount seen by the two auth functions, in the reference currency if the policy is expressed in it
+    let mut two_auth_amount = amount;
+    let mut two_auth_outcome = TwoAuthOutcome::NoPolicy;
     if !self_transfer{
-        check_two_auth(two_auth, two_auth_signer, amount, current_time, ctx.accounts.idendity_receiver.key())?;
+        if let Some(two_auth_parameters) = two_auth {
+            two_auth_amount = two_auth::get_two_auth_amount(amount, two_auth_parameters, accounts.price_account, current_time)?;
+        }
+        let general_white_list = accounts.general_white_list;
+        let idendity_receiver = accounts.idendity_receiver;
+        two_auth_outcome = check_two_auth(two_auth, two_auth_signer, two_auth_amount, 1, current_time, idendity_receiver.key(), &idendity_receiver.issuers, general_white_list)?;
     }
-    let sender_issuers = &ctx.accounts.idendity_sender.issuers;
-    let receiver_issuers = &ctx.accounts.idendity_receiver.issuers;
-    let allowed_issuers =  &ctx.accounts.wrapper_account.list_issuer;
+    let sender_issuers = &accounts.idendity_sender.issuers;
+    let receiver_issuers = &accounts.idendity_receiver.issuers;
+    let allowed_issuers =  &accounts.wrapper_account.list_issuer;
 
 
     check_idendities(sender_issuers, allowed_issuers,current_time)?;
@@ -64,12 +302,150 @@ pub fn _transfer(ctx: Context<Transfer>, amount: u64) -> Result<()> {
         return Ok(());
     }
 
+    accounts.controls.check_limits(accounts.wrapper_account, &source.key(), &destination.key(), amount, current_time)?;
+    let fee = fees::collect_fee(accounts.wrapper_account, source, accounts.fee_collector, amount)?;
     source.amount = source.amount.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
     destination.amount = destination.amount.checked_add(amount).ok_or(TransferError::Overflow)?;
 
+    // The transfer is done, the two auth counters can take it into account
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth::commit_two_auth(two_auth_amount, 1, two_auth_parameters, current_time);
+    }
+
+    emit!(TransferEvent {
+        source_wrapped_account: source.key(),
+        destination_wrapped_account: destination.key(),
+        amount,
+        memo,
+        fee,
+        two_auth: two_auth_outcome,
+        time: current_time,
+    });
+
+    Ok(())
+}
+
+/*
+    Same checks as transfer for every destination, the two auth is evaluated once on the total amount
+    (for each receiver, as the white lists and allowed issuers depend on it) and the counters are committed once
+*/
+pub fn _batch_transfer<'info>(ctx: Context<'_, '_, 'info, 'info, BatchTransfer<'info>>, amounts: Vec<u64>, memo: Option<String>) -> Result<()> {
+    let remaining_accounts = ctx.remaining_accounts;
+    if amounts.is_empty() || remaining_accounts.len() != amounts.len() * 4 {
+        return Err(TransferError::InvalidBatchAccounts.into());
+    }
+
+    let source = &mut ctx.accounts.source_wrapped_account;
+    let total_amount = amounts.iter().try_fold(0u64, |total, amount| total.checked_add(*amount)).ok_or(TransferError::Overflow)?;
+    if total_amount > source.amount {
+        return Err(TransferError::InsufficientFunds.into());
+    }
+    // A batch can't carry travel rule records, the transfers from the threshold go through transfer
+    if amounts.iter().any(|amount| ctx.accounts.wrapper_account.needs_travel_rule_record(*amount)) {
+        return Err(TravelRuleError::BatchAboveThreshold.into());
+    }
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let allowed_issuers = &ctx.accounts.wrapper_account.list_issuer;
+
+    check_idendity_not_recovered(&ctx.accounts.idendity_sender)?;
+    blocklist::check_not_blocked(&ctx.accounts.sender_blocklist_entry)?;
+    check_idendities(&ctx.accounts.idendity_sender.issuers, allowed_issuers, current_time)?;
+
+    let mut destinations: Vec<Account<WrappedTokenAccount>> = Vec::with_capacity(amounts.len());
+    let mut idendities_receiver: Vec<Account<IdAccount>> = Vec::with_capacity(amounts.len());
+    for accounts in remaining_accounts.chunks(4) {
+        let (destination, idendity_receiver) = load_batch_destination(accounts, source, ctx.program_id)?;
+        // Each destination is deserialized on its own, a duplicate would overwrite the other amount
+        if destination.key() == source.key() || destinations.iter().any(|d| d.key() == destination.key()) {
+            return Err(TransferError::InvalidBatchDestination.into());
+        }
+        check_memo(&memo, destination.require_memo)?;
+        check_idendity_not_recovered(&idendity_receiver)?;
+        check_idendities(&idendity_receiver.issuers, allowed_issuers, current_time)?;
+
+        destinations.push(destination);
+        idendities_receiver.push(idendity_receiver);
+    }
+
+    let two_auth = &mut ctx.accounts.two_auth.two_auth;
+    let mut two_auth_amount = total_amount;
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth_amount = two_auth::get_two_auth_amount(total_amount, two_auth_parameters, ctx.accounts.price_account.as_deref(), current_time)?;
+    }
+    let general_white_list = ctx.accounts.general_white_list.as_deref();
+    // As for the baseline limits, each destination counts as a transaction
+    let transactions = amounts.len() as u64;
+    let mut two_auth_outcomes = Vec::with_capacity(idendities_receiver.len());
+    for idendity_receiver in &idendities_receiver {
+        two_auth_outcomes.push(check_two_auth(two_auth, &ctx.accounts.two_auth_signer, two_auth_amount, transactions, current_time, idendity_receiver.key(), &idendity_receiver.issuers, general_white_list)?);
+    }
+
+    limits::check_baseline_limits(&ctx.accounts.wrapper_account, ctx.accounts.limit_counter.as_deref_mut(), total_amount, transactions, current_time)?;
+
+    source.last_tx = current_time;
+    source.amount = source.amount.checked_sub(total_amount).ok_or(TransferError::InsufficientFunds)?;
+    for ((destination, amount), two_auth_outcome) in destinations.iter_mut().zip(amounts).zip(two_auth_outcomes) {
+        let fee = fees::collect_fee(&ctx.accounts.wrapper_account, source, ctx.accounts.fee_collector.as_mut(), amount)?;
+        destination.amount = destination.amount.checked_add(amount).ok_or(TransferError::Overflow)?;
+        // Remaining accounts are not serialized by anchor
+        destination.exit(ctx.program_id)?;
+
+        emit!(TransferEvent {
+            source_wrapped_account: source.key(),
+            destination_wrapped_account: destination.key(),
+            amount,
+            memo: memo.clone(),
+            fee,
+            two_auth: two_auth_outcome,
+            time: current_time,
+        });
+    }
+
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth::commit_two_auth(two_auth_amount, transactions, two_auth_parameters, current_time);
+    }
+
     Ok(())
 }
 
+/*
+    Loads a (destination_wrapped_account, destination_owner, idendity_receiver, receiver_blocklist_entry) group with the constraints of Transfer
+*/
+fn load_batch_destination<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    source: &WrappedTokenAccount,
+    program_id: &Pubkey,
+) -> Result<(Account<'info, WrappedTokenAccount>, Account<'info, IdAccount>)> {
+    let destination = Account::<WrappedTokenAccount>::try_from(&accounts[0])?;
+    let destination_owner = &accounts[1];
+    let idendity_receiver = Account::<IdAccount>::try_from(&accounts[2])?;
+
+    if !accounts[0].is_writable
+        || destination.wrapper_account != source.wrapper_account
+        || destination.mint != source.mint
+        || destination.owner != destination_owner.key()
+    {
+        return Err(TransferError::InvalidBatchDestination.into());
+    }
+    let (idendity_address, _) = Pubkey::find_program_address(&[b"identity", destination_owner.key().as_ref()], program_id);
+    if idendity_receiver.key() != idendity_address {
+        return Err(TransferError::InvalidBatchDestination.into());
+    }
+    blocklist::check_not_blocked_at(&accounts[3], &source.wrapper_account, &destination_owner.key(), program_id)?;
+
+    Ok((destination, idendity_receiver))
+}
+
+
+#[inline(always)]
+pub fn check_memo(memo: &Option<String>, require_memo: bool) -> Result<()> {
+    match memo {
+        Some(memo) if memo.len() > MAX_MEMO_LEN as usize => Err(TransferError::MemoTooLong.into()),
+        None if require_memo => Err(TransferError::MemoRequired.into()),
+        _ => Ok(()),
+    }
+}
 
 /*
 Check that at least one of the idendity issuer is active and not expired and among the allowed issuers
@@ -87,10 +463,11 @@ pub fn check_idendities(user_issuers: &Vec<Issuer>, allowed_issuers: &Vec<Pubkey
 
 
 #[inline(always)]
-pub fn check_two_auth(two_auth: &mut Option<TwoAuthParameters>, two_auth_signer: &Option<Signer>, amount: u64, current_time: i64, receiver: Pubkey) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn check_two_auth(two_auth: &Option<TwoAuthParameters>, two_auth_signer: &Option<Signer>, amount: u64, transactions: u64, current_time: i64, receiver: Pubkey, receiver_issuers: &[Issuer], general_white_list: Option<&GeneralWhiteList>) -> Result<TwoAuthOutcome> {
     if two_auth.is_some() {
-        let two_auth_parameters = two_auth.as_mut().unwrap();
-        let functions  = &mut two_auth_parameters.functions;
+        let two_auth_parameters = two_auth.as_ref().unwrap();
+        two_auth::check_general_white_list(general_white_list, two_auth_parameters)?;
 
         match two_auth_signer {
             Some(signer) => {
@@ -98,13 +475,14 @@ pub fn check_two_auth(two_auth: &mut Option<TwoAuthParameters>, two_auth_signer:
                     return Err(TwoAuthError::WrongApproval.into());
                 } 
                 // if we have a proper two auth signature, no need to check if the two auth is needed 
+                return Ok(TwoAuthOutcome::Approved);
             }
-            None => {if two_auth::apply_two_auth_functions(amount, functions, current_time, receiver) {
+            None => {if two_auth::need_two_auth(amount, transactions, two_auth_parameters, current_time, receiver, receiver_issuers, general_white_list) {
                         return Err(TwoAuthError::NeedTwoAuthApproval.into());
                         }
-                        return Ok(()); // No need for two auth
+                        return Ok(TwoAuthOutcome::NotNeeded); // No need for two auth
                     }
         }
     }
-    Ok(())
+    Ok(TwoAuthOutcome::NoPolicy)
 }
\ No newline at end of file
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    check_idendities, check_idendity_not_recovered,
+    error::{IdendityError, TravelRuleError},
+    events::{TravelRuleRecordCreated, TravelRuleThresholdSet},
+    IdAccount, TravelRuleRecord, WrappedTokenAccount, WrapperAccount,
+};
+
+#[derive(Accounts)]
+pub struct SetTravelRuleThreshold<'info> {
+    #[account(mut, seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateTravelRuleRecord<'info> {
+    #[account(init, seeds=[b"travel_rule", source_wrapped_account.key().as_ref(), id.to_le_bytes().as_ref()], bump, payer=source_owner, space=TravelRuleRecord::LEN)]
+    pub travel_rule_record: Account<'info, TravelRuleRecord>,
+    #[account(has_one=wrapper_account)]
+    pub source_wrapped_account: Account<'info, WrappedTokenAccount>,
+    #[account(mut, constraint = source_wrapped_account.owner == source_owner.key())]
+    pub source_owner: Signer<'info>,
+    #[account(seeds = [b"identity", source_owner.key().as_ref()], bump)]
+    pub idendity_sender: Account<'info, IdAccount>,
+    pub issuer: Signer<'info>, // One of the sender's issuers, allowed by the wrapper
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn _set_travel_rule_threshold(ctx: Context<SetTravelRuleThreshold>, threshold: Option<u64>) -> Result<()> {
+    let wrapper_account = &mut ctx.accounts.wrapper_account;
+    wrapper_account.travel_rule_threshold = threshold;
+
+    emit!(TravelRuleThresholdSet {
+        wrapper_account: wrapper_account.key(),
+        threshold,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/*
+    The issuer attests the off chain data behind `data_hash` for a transfer of `amount` from the source to `destination`
+*/
+pub fn _create_travel_rule_record(ctx: Context<CreateTravelRuleRecord>, _id: u64, destination: Pubkey, amount: u64, data_hash: [u8; 32]) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let issuer = ctx.accounts.issuer.key();
+    let idendity_sender = &ctx.accounts.idendity_sender;
+    check_idendity_not_recovered(idendity_sender)?;
+
+    if !ctx.accounts.wrapper_account.list_issuer.contains(&issuer) {
+        return Err(IdendityError::InvalidIdendity.into());
+    }
+    // The signer has to be a valid issuer of the sender's identity
+    check_idendities(&idendity_sender.issuers, &vec![issuer], current_time)?;
+
+    let travel_rule_record = &mut ctx.accounts.travel_rule_record;
+    travel_rule_record.wrapper_account = ctx.accounts.wrapper_account.key();
+    travel_rule_record.source_wrapped_account = ctx.accounts.source_wrapped_account.key();
+    travel_rule_record.destination = destination;
+    travel_rule_record.amount = amount;
+    travel_rule_record.data_hash = data_hash;
+    travel_rule_record.issuer = issuer;
+    travel_rule_record.created_at = current_time;
+
+    emit!(TravelRuleRecordCreated {
+        travel_rule_record: travel_rule_record.key(),
+        source_wrapped_account: travel_rule_record.source_wrapped_account,
+        destination,
+        amount,
+        data_hash,
+        issuer,
+        time: current_time,
+    });
+    Ok(())
+}
+
+/*
+    A record is required from the threshold of the wrapper, and any record passed must be bound to this transfer as it is closed by it
+*/
+pub fn check_travel_rule(wrapper_account: &WrapperAccount, travel_rule_record: Option<&TravelRuleRecord>, source_wrapped_account: &Pubkey, destination: &Pubkey, amount: u64) -> Result<()> {
+    match travel_rule_record {
+        Some(record) if !record.is_bound_to(source_wrapped_account, destination, amount) => Err(TravelRuleError::RecordMismatch.into()),
+        Some(_) => Ok(()),
+        None if wrapper_account.needs_travel_rule_record(amount) => Err(TravelRuleError::RecordRequired.into()),
+        None => Ok(()),
+    }
+}
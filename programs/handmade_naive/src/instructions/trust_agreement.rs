@@ -0,0 +1,197 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    check_idendities, check_idendity_not_recovered, check_memo, check_two_auth,
+    error::{TransferError, TrustAgreementError},
+    events::{TransferEvent, TrustAgreementCreated, TrustAgreementRevoked},
+    fees, transfer_from_wrapper, two_auth, TransferControls, GeneralWhiteList, IdAccount, LimitCounter, PriceAccount, TravelRuleRecord, Treasury, TrustAgreement, TwoAuth, WrappedTokenAccount,
+    WrapperAccount,
+};
+
+#[derive(Accounts)]
+pub struct CreateTrustAgreement<'info> {
+    #[account(init, seeds=[b"trust_agreement", wrapper_a.key().as_ref(), wrapper_b.key().as_ref()], bump, payer=payer, space=TrustAgreement::LEN)]
+    pub trust_agreement: Account<'info, TrustAgreement>,
+    #[account(seeds=[b"wrapper", approver_a.key().as_ref()], bump)]
+    pub wrapper_a: Account<'info, WrapperAccount>,
+    pub approver_a: Signer<'info>,
+    #[account(seeds=[b"wrapper", approver_b.key().as_ref()], bump)]
+    pub wrapper_b: Account<'info, WrapperAccount>,
+    pub approver_b: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Either approver can end the agreement alone
+#[derive(Accounts)]
+pub struct RevokeTrustAgreement<'info> {
+    #[account(mut, close=approver, constraint = trust_agreement.links(&wrapper_account.key()))]
+    pub trust_agreement: Account<'info, TrustAgreement>,
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    #[account(mut)]
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CrossWrapperTransfer<'info> {
+    #[account(constraint = trust_agreement.is_between(&source_wrapper_account.key(), &destination_wrapper_account.key()))]
+    pub trust_agreement: Account<'info, TrustAgreement>,
+    #[account(mut, constraint = source_wrapped_account.wrapper_account == source_wrapper_account.key())]
+    pub source_wrapped_account: Box<Account<'info, WrappedTokenAccount>>,
+    #[account(mut, constraint = source_wrapped_account.owner == source_owner.key())]
+    pub source_owner: Signer<'info>,
+    #[account(seeds = [b"identity", source_owner.key().as_ref()], bump)]
+    pub idendity_sender: Box<Account<'info, IdAccount>>,
+    /// CHECK: Has to be empty, the sender in the source wrapper is blocked otherwise
+    #[account(seeds=[b"blocklist", source_wrapper_account.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", source_wrapper_account.key().as_ref(), mint.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub two_auth: Box<Account<'info,TwoAuth>>,
+    #[account(mut, constraint = destination_wrapped_account.wrapper_account == destination_wrapper_account.key() && destination_wrapped_account.mint == mint.key())]
+    pub destination_wrapped_account: Box<Account<'info, WrappedTokenAccount>>,
+    /// CHECK: The owner of the destination account
+    #[account(constraint = destination_wrapped_account.owner == destination_owner.key())]
+    pub destination_owner: AccountInfo<'info>,
+    #[account(seeds = [b"identity", destination_owner.key().as_ref()], bump)]
+    pub idendity_receiver: Box<Account<'info, IdAccount>>,
+    /// CHECK: Has to be empty, the receiver in the destination wrapper is blocked otherwise
+    #[account(seeds=[b"blocklist", destination_wrapper_account.key().as_ref(), destination_owner.key().as_ref()], bump)]
+    pub receiver_blocklist_entry: UncheckedAccount<'info>,
+    pub two_auth_signer: Option<Signer<'info>>,
+    pub general_white_list: Option<Box<Account<'info, GeneralWhiteList>>>,
+    #[account(seeds=[b"price", source_wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub price_account: Option<Box<Account<'info, PriceAccount>>>,
+    #[account(mut)]
+    pub fee_collector: Option<Box<Account<'info, WrappedTokenAccount>>>, // Needed when the transfer has a fee in the source wrapper
+    #[account(mut, seeds=[b"limit_counter", source_wrapped_account.key().as_ref()], bump)]
+    pub limit_counter: Option<Box<Account<'info, LimitCounter>>>, // Needed when the source wrapper has baseline limits
+    #[account(mut, close = source_owner)]
+    pub travel_rule_record: Option<Box<Account<'info, TravelRuleRecord>>>, // Needed from the travel rule threshold of the source wrapper, closed by the transfer
+    #[account(seeds=[b"wrapper", source_approver.key().as_ref()], bump)]
+    pub source_wrapper_account: Box<Account<'info, WrapperAccount>>,
+    /// CHECK: The approver of the source wrapper
+    pub source_approver: UncheckedAccount<'info>,
+    pub destination_wrapper_account: Box<Account<'info, WrapperAccount>>,
+    #[account(mut, token::mint = mint, token::authority = source_wrapper_account)]
+    pub source_wrapper_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, token::mint = mint, token::authority = destination_wrapper_account)]
+    pub destination_wrapper_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, seeds=[b"treasury", source_wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub source_treasury: Box<Account<'info, Treasury>>,
+    #[account(mut, seeds=[b"treasury", destination_wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub destination_treasury: Box<Account<'info, Treasury>>,
+    #[account(constraint = source_wrapped_account.mint == mint.key(), mint::token_program = token_program)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn _create_trust_agreement(ctx: Context<CreateTrustAgreement>) -> Result<()> {
+    let wrapper_a = ctx.accounts.wrapper_a.key();
+    let wrapper_b = ctx.accounts.wrapper_b.key();
+    if wrapper_a >= wrapper_b {
+        return Err(TrustAgreementError::InvalidWrapperOrder.into());
+    }
+
+    let trust_agreement = &mut ctx.accounts.trust_agreement;
+    trust_agreement.wrapper_a = wrapper_a;
+    trust_agreement.wrapper_b = wrapper_b;
+
+    emit!(TrustAgreementCreated {
+        trust_agreement: trust_agreement.key(),
+        wrapper_a,
+        wrapper_b,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// The trust agreement account is closed by anchor, the rent goes to the approver
+pub fn _revoke_trust_agreement(ctx: Context<RevokeTrustAgreement>) -> Result<()> {
+    emit!(TrustAgreementRevoked {
+        trust_agreement: ctx.accounts.trust_agreement.key(),
+        wrapper_account: ctx.accounts.wrapper_account.key(),
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/*
+    Same checks as transfer, the issuers of both wrappers being accepted for both identities
+    The underlying tokens move from the source wrapper to the destination wrapper, so both stay fully backed
+    The fee and the two auth are the ones of the source wrapper
+*/
+pub fn _cross_wrapper_transfer(ctx: Context<CrossWrapperTransfer>, amount: u64, memo: Option<String>) -> Result<()> {
+    let source = &mut ctx.accounts.source_wrapped_account;
+    let destination = &mut ctx.accounts.destination_wrapped_account;
+
+    if amount > source.amount {
+        return Err(TransferError::InsufficientFunds.into());
+    }
+    check_memo(&memo, destination.require_memo)?;
+
+    let idendity_sender = &ctx.accounts.idendity_sender;
+    let idendity_receiver = &ctx.accounts.idendity_receiver;
+    check_idendity_not_recovered(idendity_sender)?;
+    check_idendity_not_recovered(idendity_receiver)?;
+    let controls = TransferControls {
+        sender_blocklist_entry: &ctx.accounts.sender_blocklist_entry,
+        receiver_blocklist_entry: &ctx.accounts.receiver_blocklist_entry,
+        limit_counter: ctx.accounts.limit_counter.as_deref_mut().map(|c| &mut **c),
+        travel_rule_record: ctx.accounts.travel_rule_record.as_deref().map(|r| &**r),
+    };
+    controls.check_not_blocked()?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let two_auth = &mut ctx.accounts.two_auth.two_auth;
+    let mut two_auth_amount = amount;
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth_amount = two_auth::get_two_auth_amount(amount, two_auth_parameters, ctx.accounts.price_account.as_deref().map(|p| &**p), current_time)?;
+    }
+    let general_white_list = ctx.accounts.general_white_list.as_deref().map(|g| &**g);
+    let two_auth_outcome = check_two_auth(two_auth, &ctx.accounts.two_auth_signer, two_auth_amount, 1, current_time, idendity_receiver.key(), &idendity_receiver.issuers, general_white_list)?;
+
+    let mut allowed_issuers = ctx.accounts.source_wrapper_account.list_issuer.clone();
+    allowed_issuers.extend_from_slice(&ctx.accounts.destination_wrapper_account.list_issuer);
+    check_idendities(&idendity_sender.issuers, &allowed_issuers, current_time)?;
+    check_idendities(&idendity_receiver.issuers, &allowed_issuers, current_time)?;
+
+    source.last_tx = current_time;
+    controls.check_limits(&ctx.accounts.source_wrapper_account, &source.key(), &destination.key(), amount, current_time)?;
+    let fee = fees::collect_fee(&ctx.accounts.source_wrapper_account, source, ctx.accounts.fee_collector.as_deref_mut(), amount)?;
+    source.amount = source.amount.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+    destination.amount = destination.amount.checked_add(amount).ok_or(TransferError::Overflow)?;
+
+    let source_treasury = &mut ctx.accounts.source_treasury;
+    source_treasury.user_supply = source_treasury.user_supply.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+    let destination_treasury = &mut ctx.accounts.destination_treasury;
+    destination_treasury.user_supply = destination_treasury.user_supply.checked_add(amount).ok_or(TransferError::Overflow)?;
+
+    transfer_from_wrapper(
+        &ctx.accounts.token_program,
+        &ctx.accounts.source_wrapper_token_account,
+        &ctx.accounts.destination_wrapper_token_account,
+        &ctx.accounts.source_wrapper_account,
+        &ctx.accounts.source_approver.key(),
+        ctx.bumps.source_wrapper_account,
+        amount,
+    )?;
+
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth::commit_two_auth(two_auth_amount, 1, two_auth_parameters, current_time);
+    }
+
+    emit!(TransferEvent {
+        source_wrapped_account: source.key(),
+        destination_wrapped_account: destination.key(),
+        amount,
+        memo,
+        fee,
+        two_auth: two_auth_outcome,
+        time: current_time,
+    });
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
 
-use crate::{check_idendity_not_recovered, error::TwoAuthError, CircularTimeWindow, IdAccount, TwoAuth, TwoAuthArgs, TwoAuthFunction, TwoAuthParameters, WrapperAccount};
+use crate::{check_idendity_not_recovered, error::TwoAuthError, events::{TwoAuthInitialized, TwoAuthTightened, TwoAuthUpdateApplied, TwoAuthUpdateCancelled, TwoAuthUpdateScheduled, TwoAuthUpdated}, CircularTimeWindow, GeneralWhiteList, IdAccount, Issuer, PendingTwoAuthUpdate, PriceAccount, TwoAuth, TwoAuthArgs, TwoAuthFunction, TwoAuthParameters, TwoAuthRule, TwoAuthRuleNode, WrapperAccount};
 
 #[derive(Accounts)]
 #[instruction(two_auth_args: Option<TwoAuthArgs>)]
@@ -11,12 +12,13 @@ pub struct InitTwoAuth<'info> {
     pub wrapper_account: Account<'info, WrapperAccount>,
     /// CHECK: The approver of the wrapper
     pub approver: UncheckedAccount<'info>,
-    #[account(init, seeds=[b"two_auth", wrapper_account.key().as_ref(), owner.key().as_ref()], bump, payer=payer, space=TwoAuth::get_init_len(&two_auth_args))]
+    #[account(init, seeds=[b"two_auth", wrapper_account.key().as_ref(), mint.key().as_ref(), owner.key().as_ref()], bump, payer=payer, space=TwoAuth::get_init_len(&two_auth_args))]
     pub two_auth: Account<'info,TwoAuth>,
     pub two_auth_entity: Option<Signer<'info>>,
     #[account(mut)]
     pub payer: Signer<'info>,
     pub owner: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>, // The two auth is specific to a mint: the max values are in the mint's units
     pub system_program: Program<'info, System>,
 }
 
@@ -29,15 +31,114 @@ pub struct UpdateTwoAuth<'info> {
     pub wrapper_account: Account<'info, WrapperAccount>,
     /// CHECK: The approver of the wrapper
     pub approver: UncheckedAccount<'info>,
-    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), owner.key().as_ref()], bump, realloc=TwoAuth::get_init_len(&two_auth_args), realloc::payer=owner, realloc::zero=true)]
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), mint.key().as_ref(), owner.key().as_ref()], bump, realloc=TwoAuth::get_init_len(&two_auth_args), realloc::payer=owner, realloc::zero=true)]
     pub two_auth: Account<'info,TwoAuth>,
     pub two_auth_entity: Option<Signer<'info>>,
     pub old_two_auth_entity: Option<Signer<'info>>,
     #[account(mut)]
     pub owner: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(two_auth_args: Option<TwoAuthArgs>)]
+pub struct ScheduleTwoAuthUpdate<'info> {
+    #[account(seeds = [b"identity", owner.key().as_ref()], bump)]
+    pub idendity: Account<'info, IdAccount>,
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    /// CHECK: The approver of the wrapper
+    pub approver: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), mint.key().as_ref(), owner.key().as_ref()], bump, realloc=two_auth.get_schedule_update_len(&two_auth_args), realloc::payer=owner, realloc::zero=false)]
+    pub two_auth: Account<'info,TwoAuth>,
+    pub two_auth_entity: Option<Signer<'info>>, // Only needed to change the two auth entity
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTwoAuthUpdate<'info> {
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), mint.key().as_ref(), owner.key().as_ref()], bump)]
+    pub two_auth: Account<'info,TwoAuth>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    /// CHECK: The owner of the two auth account
+    pub owner: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub authority: Signer<'info>, // The owner or the current two auth entity
+}
+
+#[derive(Accounts)]
+pub struct ApplyTwoAuthUpdate<'info> {
+    #[account(seeds = [b"identity", owner.key().as_ref()], bump)]
+    pub idendity: Account<'info, IdAccount>,
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    /// CHECK: The approver of the wrapper
+    pub approver: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), mint.key().as_ref(), owner.key().as_ref()], bump, realloc=two_auth.get_apply_update_len(), realloc::payer=owner, realloc::zero=true)]
+    pub two_auth: Account<'info,TwoAuth>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(rule: TwoAuthRule)]
+pub struct TightenTwoAuth<'info> {
+    #[account(seeds = [b"identity", owner.key().as_ref()], bump)]
+    pub idendity: Account<'info, IdAccount>,
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    /// CHECK: The approver of the wrapper
+    pub approver: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), mint.key().as_ref(), owner.key().as_ref()], bump, realloc=two_auth.get_tighten_len(&rule), realloc::payer=owner, realloc::zero=false)]
+    pub two_auth: Account<'info,TwoAuth>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, receiver: Pubkey)]
+pub struct SimulateTwoAuth<'info> {
+    #[account(seeds=[b"two_auth", wrapper_account.key().as_ref(), mint.key().as_ref(), owner.key().as_ref()], bump)]
+    pub two_auth: Account<'info,TwoAuth>,
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    /// CHECK: The owner of the two auth account, reading the policy doesn't need its signature
+    pub owner: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub general_white_list: Option<Account<'info, GeneralWhiteList>>,
+    #[account(seeds=[b"price", wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub price_account: Option<Account<'info, PriceAccount>>,
+    #[account(constraint = idendity_receiver.key() == receiver)]
+    pub idendity_receiver: Option<Account<'info, IdAccount>>, // Needed when the policy has allowed issuers
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TwoAuthSimulation {
+    pub need_two_auth: bool,
+    pub triggered_by: Option<TwoAuthFunctionLocation>,
+    pub counters: Vec<CounterHeadroom>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Debug)]
+pub enum TwoAuthFunctionLocation {
+    Function(u8), // Index in TwoAuthParameters.functions
+    RuleNode(u8), // Index in TwoAuthRule.nodes
+    AllowedIssuers, // The receiver is not attested by any of TwoAuthParameters.allowed_issuers
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CounterHeadroom {
+    pub location: TwoAuthFunctionLocation,
+    pub remaining: u64, // Amount (or number of transfers) that can still go through without the two auth
+}
+
 
 pub fn _initialize_two_auth(
     ctx: Context<InitTwoAuth>,
@@ -57,17 +158,20 @@ pub fn _initialize_two_auth(
                 return Err(TwoAuthError::NeedTwoAuthApproval.into());
             }
             let time = Clock::get()?.unix_timestamp;
-            let two_auth_parameters = TwoAuthParameters{
-                functions: two_auth_args.functions.iter().map(|function| init_functions(function, time)).collect(),
-                two_auth_entity: two_auth_entity.as_ref().unwrap().key(),
-                allowed_issuers: two_auth_args.allowed_issuers.clone(),
-            };
-            two_auth.two_auth = Some(two_auth_parameters);
+            two_auth.two_auth = Some(init_two_auth_parameters(&two_auth_args, two_auth_entity.as_ref().unwrap().key(), time)?);
         }
         None => {
             two_auth.two_auth = None;
         }
     }
+    two_auth.pending_update = None;
+
+    emit!(TwoAuthInitialized {
+        two_auth: two_auth.key(),
+        owner: ctx.accounts.owner.key(),
+        two_auth_entity: two_auth.two_auth.as_ref().map(|p| p.two_auth_entity),
+        time: Clock::get()?.unix_timestamp,
+    });
 
     Ok(())
 }
@@ -96,18 +200,174 @@ pub fn _update_two_auth(
             if two_auth_entity.is_none(){
                 return Err(TwoAuthError::NeedTwoAuthApproval.into());
             }
-            let two_auth_parameters = TwoAuthParameters{
-                functions: two_auth_args.functions.iter().map(|function| init_functions(function, time)).collect(),
-                two_auth_entity: two_auth_entity.as_ref().unwrap().key(),
-                allowed_issuers: two_auth_args.allowed_issuers.clone(),
-            };
-            two_auth.two_auth = Some(two_auth_parameters);
+            two_auth.two_auth = Some(init_two_auth_parameters(&two_auth_args, two_auth_entity.as_ref().unwrap().key(), time)?);
         }
         None => {
             two_auth.two_auth = None;
         }
     }
+    // The two auth entity agreed on this update, it replaces any scheduled one
+    two_auth.pending_update = None;
 
+    emit!(TwoAuthUpdated {
+        two_auth: two_auth.key(),
+        owner: ctx.accounts.owner.key(),
+        two_auth_entity: two_auth.two_auth.as_ref().map(|p| p.two_auth_entity),
+        time: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/*
+    The owner alone can remove or loosen the policy, but only after the downgrade delay of the current policy
+    This way the owner is not locked if the two auth entity disappears, and the entity can still block a stolen key
+*/
+pub fn _schedule_two_auth_update(
+    ctx: Context<ScheduleTwoAuthUpdate>,
+    two_auth_args: Option<TwoAuthArgs>
+) -> Result<()> {
+
+    let idendity = &ctx.accounts.idendity;
+    check_idendity_not_recovered(idendity)?;
+
+    let two_auth = &mut ctx.accounts.two_auth;
+    // Without any policy, update_two_auth doesn't need the two auth entity
+    let current_two_auth = two_auth.two_auth.as_ref().ok_or(TwoAuthError::NoTwoAuthPolicy)?;
+    let time = Clock::get()?.unix_timestamp;
+
+    let new_two_auth = match two_auth_args {
+        Some(two_auth_args) => {
+            let two_auth_entity = match &ctx.accounts.two_auth_entity {
+                Some(two_auth_entity) => two_auth_entity.key(),
+                None => current_two_auth.two_auth_entity,
+            };
+            Some(init_two_auth_parameters(&two_auth_args, two_auth_entity, time)?)
+        }
+        None => None,
+    };
+
+    let effective_at = time.saturating_add(current_two_auth.downgrade_delay);
+
+    emit!(TwoAuthUpdateScheduled {
+        two_auth: two_auth.key(),
+        owner: ctx.accounts.owner.key(),
+        two_auth_entity: new_two_auth.as_ref().map(|p| p.two_auth_entity),
+        effective_at,
+        time,
+    });
+
+    two_auth.pending_update = Some(PendingTwoAuthUpdate {
+        two_auth: new_two_auth,
+        effective_at,
+    });
+
+    Ok(())
+}
+
+pub fn _cancel_two_auth_update(ctx: Context<CancelTwoAuthUpdate>) -> Result<()> {
+    let two_auth = &mut ctx.accounts.two_auth;
+    let authority = ctx.accounts.authority.key();
+    let time = Clock::get()?.unix_timestamp;
+
+    two_auth.check_cancel_update(authority, ctx.accounts.owner.key(), time)?;
+    two_auth.pending_update = None;
+
+    emit!(TwoAuthUpdateCancelled {
+        two_auth: two_auth.key(),
+        authority,
+        time,
+    });
+    Ok(())
+}
+
+pub fn _apply_two_auth_update(ctx: Context<ApplyTwoAuthUpdate>) -> Result<()> {
+    let idendity = &ctx.accounts.idendity;
+    check_idendity_not_recovered(idendity)?;
+
+    let two_auth = &mut ctx.accounts.two_auth;
+    let pending_update = two_auth.pending_update.take().ok_or(TwoAuthError::NoPendingUpdate)?;
+
+    let time = Clock::get()?.unix_timestamp;
+    if time < pending_update.effective_at {
+        return Err(TwoAuthError::DowngradeDelayNotPassed.into());
+    }
+
+    two_auth.two_auth = pending_update.two_auth;
+
+    emit!(TwoAuthUpdateApplied {
+        two_auth: two_auth.key(),
+        owner: ctx.accounts.owner.key(),
+        two_auth_entity: two_auth.two_auth.as_ref().map(|p| p.two_auth_entity),
+        time,
+    });
+    Ok(())
+}
+
+/*
+    Tightening doesn't need the two auth entity: the new rule is added to the current one,
+    the two auth is then needed when any of them requires it
+*/
+pub fn _tighten_two_auth(ctx: Context<TightenTwoAuth>, rule: TwoAuthRule) -> Result<()> {
+    let idendity = &ctx.accounts.idendity;
+    check_idendity_not_recovered(idendity)?;
+
+    let two_auth_parameters = ctx.accounts.two_auth.two_auth.as_mut().ok_or(TwoAuthError::NoTwoAuthPolicy)?;
+    if !rule.is_valid() {
+        return Err(TwoAuthError::InvalidRule.into());
+    }
+
+    let time = Clock::get()?.unix_timestamp;
+    let rule = init_rule(&rule, time);
+    two_auth_parameters.rule = match &two_auth_parameters.rule {
+        Some(current_rule) => Some(current_rule.or(&rule).ok_or(TwoAuthError::InvalidRule)?),
+        None => Some(rule),
+    };
+
+    emit!(TwoAuthTightened {
+        two_auth: ctx.accounts.two_auth.key(),
+        owner: ctx.accounts.owner.key(),
+        time,
+    });
+    Ok(())
+}
+
+/*
+    Read only: returns (via the return data) what a transfer of `amount` to `receiver` would need, without touching the counters
+    `receiver` is the identity account of the destination owner, as in transfer
+*/
+pub fn _simulate_two_auth(
+    ctx: Context<SimulateTwoAuth>,
+    amount: u64,
+    receiver: Pubkey,
+) -> Result<TwoAuthSimulation> {
+    let time = Clock::get()?.unix_timestamp;
+    let general_white_list = ctx.accounts.general_white_list.as_deref();
+
+    match &ctx.accounts.two_auth.two_auth {
+        Some(two_auth_parameters) => {
+            check_general_white_list(general_white_list, two_auth_parameters)?;
+            let amount = get_two_auth_amount(amount, two_auth_parameters, ctx.accounts.price_account.as_deref(), time)?;
+            let receiver_issuers = match &ctx.accounts.idendity_receiver {
+                Some(idendity_receiver) => idendity_receiver.issuers.as_slice(),
+                None => &[],
+            };
+            Ok(simulate_need_two_auth(amount, two_auth_parameters, time, receiver, receiver_issuers, general_white_list))
+        }
+        None => Ok(TwoAuthSimulation { need_two_auth: false, triggered_by: None, counters: vec![] }),
+    }
+}
+
+#[inline(always)]
+pub fn check_general_white_list(
+    general_white_list: Option<&GeneralWhiteList>,
+    two_auth_parameters: &TwoAuthParameters,
+) -> Result<()> {
+    if let Some(general_white_list) = general_white_list {
+        if general_white_list.two_auth_entity != two_auth_parameters.two_auth_entity {
+            return Err(TwoAuthError::WrongGeneralWhiteList.into());
+        }
+    }
     Ok(())
 }
 
@@ -130,6 +390,42 @@ pub fn check_authorization_old_two_auth_entity(
     Ok(())
 }
 
+#[inline(always)]
+pub fn init_two_auth_parameters(two_auth_args: &TwoAuthArgs, two_auth_entity: Pubkey, time: i64) -> Result<TwoAuthParameters> {
+    check_rule(&two_auth_args.rule)?;
+    if two_auth_args.downgrade_delay < 0 {
+        return Err(TwoAuthError::InvalidDowngradeDelay.into());
+    }
+    if !two_auth_args.functions.iter().all(|function| function.is_valid()) {
+        return Err(TwoAuthError::InvalidDuration.into());
+    }
+    Ok(TwoAuthParameters{
+        functions: two_auth_args.functions.iter().map(|function| init_functions(function, time)).collect(),
+        rule: two_auth_args.rule.as_ref().map(|rule| init_rule(rule, time)),
+        two_auth_entity,
+        allowed_issuers: two_auth_args.allowed_issuers.clone(),
+        price_max_age: two_auth_args.price_max_age,
+        downgrade_delay: two_auth_args.downgrade_delay,
+    })
+}
+
+/*
+    Returns the amount the functions have to consider: the value in the reference currency if the max values are expressed in it
+*/
+#[inline(always)]
+pub fn get_two_auth_amount(amount: u64, two_auth_parameters: &TwoAuthParameters, price_account: Option<&PriceAccount>, time: i64) -> Result<u64> {
+    match two_auth_parameters.price_max_age {
+        Some(price_max_age) => {
+            let price_account = price_account.ok_or(TwoAuthError::MissingPrice)?;
+            if price_account.is_stale(time, price_max_age) {
+                return Err(TwoAuthError::StalePrice.into());
+            }
+            price_account.get_value(amount)
+        }
+        None => Ok(amount),
+    }
+}
+
 #[inline(always)]
 pub fn init_functions(function: &TwoAuthFunction, time: i64) -> TwoAuthFunction{
     match function {
@@ -144,73 +440,421 @@ pub fn init_functions(function: &TwoAuthFunction, time: i64) -> TwoAuthFunction{
             TwoAuthFunction::CounterWithTimeWindow { window: CircularTimeWindow::new(duration,time), max: *max }
 
         }
+        TwoAuthFunction::TransactionCounterWithTimeWindow { window, max } => {
+            let duration = window.get_duration();
+            TwoAuthFunction::TransactionCounterWithTimeWindow { window: CircularTimeWindow::new(duration,time), max: *max }
+        }
         _ => function.clone(),
     }
 }
 
+#[inline(always)]
+pub fn init_rule(rule: &TwoAuthRule, time: i64) -> TwoAuthRule{
+    let nodes = rule.nodes.iter().map(|node| match node {
+        TwoAuthRuleNode::Function(function) => TwoAuthRuleNode::Function(init_functions(function, time)),
+        _ => node.clone(),
+    }).collect();
+    TwoAuthRule { nodes }
+}
+
+#[inline(always)]
+pub fn check_rule(rule: &Option<TwoAuthRule>) -> Result<()> {
+    if let Some(rule) = rule {
+        if !rule.is_valid() {
+            return Err(TwoAuthError::InvalidRule.into());
+        }
+    }
+    Ok(())
+}
 
 // Functions from TwoAuthFunction
 
+/*
+    The two auth is handled in two phases:
+    - evaluation (evaluate_two_auth, need_two_auth): decides if the two auth is needed, without modifying anything
+    - commit (commit_two_auth): updates the counters, only once the transfer is done (with or without the two auth approval)
+*/
+
 /*
     Returns true if there is need for two auth
 */
-pub fn apply_two_auth_functions(amount: u64, functions: &mut  Vec<TwoAuthFunction>, time: i64, receiver: Pubkey) -> bool {
-    let mut need_two_auth;
-    for function in functions.iter_mut() {
-        need_two_auth = match_functions(amount, function, time, receiver);
-        if need_two_auth.is_some() {
-            return need_two_auth.unwrap();
+pub fn need_two_auth(amount: u64, transactions: u64, parameters: &TwoAuthParameters, time: i64, receiver: Pubkey, receiver_issuers: &[Issuer], general_white_list: Option<&GeneralWhiteList>) -> bool {
+    evaluate_two_auth(amount, transactions, parameters, time, receiver, receiver_issuers, general_white_list).is_some()
+}
+
+/*
+    Returns what requires the two auth, None if there is no need for two auth
+    A receiver not attested by one of the allowed issuers (if any) always needs the two auth
+    The first function giving an answer decides, so the order of the functions matters
+    Both the ordered functions and the rule are evaluated: the two auth is needed if any of them requires it
+*/
+pub fn evaluate_two_auth(amount: u64, transactions: u64, parameters: &TwoAuthParameters, time: i64, receiver: Pubkey, receiver_issuers: &[Issuer], general_white_list: Option<&GeneralWhiteList>) -> Option<TwoAuthFunctionLocation> {
+    if !parameters.allowed_issuers.is_empty() && !has_allowed_issuer(receiver_issuers, &parameters.allowed_issuers, time) {
+        return Some(TwoAuthFunctionLocation::AllowedIssuers);
+    }
+    for (index, function) in parameters.functions.iter().enumerate() {
+        if let Some(need_two_auth) = evaluate_function(amount, transactions, function, time, receiver, general_white_list) {
+            if need_two_auth {
+                return Some(TwoAuthFunctionLocation::Function(index as u8));
+            }
+            break;
+        }
+    }
+    match &parameters.rule {
+        Some(rule) if evaluate_rule(amount, transactions, rule, time, receiver, general_white_list) => {
+            Some(TwoAuthFunctionLocation::RuleNode((rule.nodes.len() - 1) as u8))
         }
+        _ => None,
     }
-    return false;
 }
 
 /*
-Returns either a boolean or None if we should continue checking the other functions
+    Same check as check_idendities, against the issuers trusted by the user instead of the wrapper ones
 */
+#[inline(always)]
+pub fn has_allowed_issuer(user_issuers: &[Issuer], allowed_issuers: &[Pubkey], time: i64) -> bool {
+    user_issuers.iter().any(|issuer| allowed_issuers.contains(&issuer.key) && issuer.active && issuer.expires_at > time)
+}
+
+/*
+    Same decision as need_two_auth
+    Also returns what triggered the two auth and the remaining headroom of every counter
+*/
+pub fn simulate_need_two_auth(amount: u64, parameters: &TwoAuthParameters, time: i64, receiver: Pubkey, receiver_issuers: &[Issuer], general_white_list: Option<&GeneralWhiteList>) -> TwoAuthSimulation {
+    let triggered_by = evaluate_two_auth(amount, 1, parameters, time, receiver, receiver_issuers, general_white_list);
+
+    let counters = get_all_functions(parameters).filter_map(|(location, function)| {
+        let (counter, max) = function.get_counter(time)?;
+        let remaining = match function {
+            // Needs the two auth past max transfers
+            TwoAuthFunction::TransactionCounterWithTimeWindow { .. } => max.saturating_sub(counter),
+            // Needs the two auth when the counter reaches max
+            _ => max.saturating_sub(counter).saturating_sub(1),
+        };
+        Some(CounterHeadroom { location, remaining })
+    }).collect();
+
+    TwoAuthSimulation {
+        need_two_auth: triggered_by.is_some(),
+        triggered_by,
+        counters,
+    }
+}
+
+/*
+    Iterates over the functions and the functions of the rule
+*/
+fn get_all_functions(parameters: &TwoAuthParameters) -> impl Iterator<Item = (TwoAuthFunctionLocation, &TwoAuthFunction)> {
+    let functions = parameters.functions.iter().enumerate().map(|(index, function)| (TwoAuthFunctionLocation::Function(index as u8), function));
+    let rule_functions = parameters.rule.iter().flat_map(|rule| rule.nodes.iter().enumerate()).filter_map(|(index, node)| match node {
+        TwoAuthRuleNode::Function(function) => Some((TwoAuthFunctionLocation::RuleNode(index as u8), function)),
+        _ => None,
+    });
+    functions.chain(rule_functions)
+}
+
+/*
+    To call once the transfer is done: every counter (functions and rule) takes the transfer into account
+    Whatever function decided, and even if the two auth entity approved the transfer
+*/
+pub fn commit_two_auth(amount: u64, transactions: u64, parameters: &mut TwoAuthParameters, time: i64) {
+    for function in parameters.functions.iter_mut() {
+        commit_function(amount, transactions, function, time);
+    }
+    if let Some(rule) = &mut parameters.rule {
+        for node in rule.nodes.iter_mut() {
+            if let TwoAuthRuleNode::Function(function) = node {
+                commit_function(amount, transactions, function, time);
+            }
+        }
+    }
+}
 
-pub fn match_functions(amount: u64, function: &mut TwoAuthFunction, time: i64, receiver: Pubkey) -> Option<bool> {
+pub fn commit_function(amount: u64, transactions: u64, function: &mut TwoAuthFunction, time: i64) {
     match function {
-        TwoAuthFunction::Always => Some(true),
-        TwoAuthFunction::OnMax { max} => if amount >= *max {Some(true)} else {None},
         TwoAuthFunction::CounterResetOnMax { max, counter } => {
-            let new_counter = counter.checked_add(amount);
-            if new_counter.is_none() || new_counter.is_some() && new_counter.unwrap() >= *max {
-                *counter = 0;
-                return Some(true);
-            }
-            return None;
+            // Reaching max is only possible with the two auth approval, which resets the counter
+            let new_counter = counter.saturating_add(amount);
+            *counter = if new_counter >= *max {0} else {new_counter};
         }
-        TwoAuthFunction::CounterResetOnTime { max, duration, counter, last_reset_time } => {
-            let diff = time.checked_sub(*last_reset_time);
-            if  diff.is_some() && diff.unwrap() > duration.get() as i64{
+        TwoAuthFunction::CounterResetOnTime { max: _, duration, counter, last_reset_time } => {
+            if time.saturating_sub(*last_reset_time) > duration.get_seconds() {
                 *counter = 0;
                 *last_reset_time = time;
             }
-            let new_counter = counter.checked_add(amount);
-            if new_counter.is_some() && new_counter.unwrap() >= *max {
-                *counter = new_counter.unwrap();
-                return Some(true);
-            } else if new_counter.is_none(){
-                *counter = 0;
-                return Some(true);
-            }
-            return None;
+            *counter = counter.saturating_add(amount);
         }
-        TwoAuthFunction::CounterWithTimeWindow { max, window } => {
-            window.add(time, amount);
-            let count = window.get_count();
-            if count >= *max {
-                return Some(true);
+        TwoAuthFunction::CounterWithTimeWindow { window, .. } => window.add(time, amount),
+        TwoAuthFunction::TransactionCounterWithTimeWindow { window, .. } => window.add(time, transactions),
+        _ => {}
+    }
+}
+
+/*
+    Returns the value of the rule, a leaf is true when its function matches
+    Every node is evaluated once, from the first to the root
+*/
+pub fn evaluate_rule(amount: u64, transactions: u64, rule: &TwoAuthRule, time: i64, receiver: Pubkey, general_white_list: Option<&GeneralWhiteList>) -> bool {
+    let mut values: Vec<bool> = Vec::with_capacity(rule.nodes.len());
+    for node in rule.nodes.iter() {
+        let value = match node {
+            TwoAuthRuleNode::Function(function) => evaluate_function(amount, transactions, function, time, receiver, general_white_list).is_some(),
+            TwoAuthRuleNode::All(children) => children.iter().all(|child| values[*child as usize]),
+            TwoAuthRuleNode::Any(children) => children.iter().any(|child| values[*child as usize]),
+            TwoAuthRuleNode::Not(child) => !values[*child as usize],
+        };
+        values.push(value);
+    }
+    values.last().copied().unwrap_or(false)
+}
+
+/*
+Returns either a boolean or None if we should continue checking the other functions
+Doesn't modify the function, see commit_function for the counters
+*/
+pub fn evaluate_function(amount: u64, transactions: u64, function: &TwoAuthFunction, time: i64, receiver: Pubkey, general_white_list: Option<&GeneralWhiteList>) -> Option<bool> {
+    match function {
+        TwoAuthFunction::Always => Some(true),
+        TwoAuthFunction::OnMax { max} => if amount >= *max {Some(true)} else {None},
+        TwoAuthFunction::CounterResetOnMax { .. }
+        | TwoAuthFunction::CounterResetOnTime { .. }
+        | TwoAuthFunction::CounterWithTimeWindow { .. } => {
+            let (counter, max) = function.get_counter(time)?;
+            match counter.checked_add(amount) {
+                Some(new_counter) if new_counter < max => None,
+                _ => Some(true),
             }
-            return None;
         }
         TwoAuthFunction::DeactivateForUserSpecificWhiteList { white_list } => {
-            if white_list.contains(&receiver) {
-                return Some(false);
+            if white_list.contains(&receiver) {Some(false)} else {None}
+        }
+        TwoAuthFunction::TransactionCounterWithTimeWindow { .. } => {
+            // Each transfer counts as 1 (a batch as its number of transfers), the two auth is needed past `max` transfers in the window
+            let (counter, max) = function.get_counter(time)?;
+            if counter.saturating_add(transactions) > max {Some(true)} else {None}
+        }
+        TwoAuthFunction::DeactivateForGeneralWhiteList => {
+            // Without the general white list account, the receiver can't be considered as white listed
+            match general_white_list {
+                Some(general_white_list) if general_white_list.white_list.contains(&receiver) => Some(false),
+                _ => None,
             }
-            return None;
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(functions: Vec<TwoAuthFunction>, rule: Option<TwoAuthRule>) -> TwoAuthParameters {
+        TwoAuthParameters {
+            functions,
+            rule,
+            two_auth_entity: Pubkey::new_unique(),
+            allowed_issuers: vec![],
+            price_max_age: None,
+            downgrade_delay: 0,
+        }
+    }
+
+    #[test]
+    fn transaction_counter_with_time_window() {
+        let day = 86400;
+        let receiver = Pubkey::new_unique();
+        let mut function = init_functions(
+            &TwoAuthFunction::TransactionCounterWithTimeWindow {
+                max: 3,
+                window: CircularTimeWindow::new(crate::Duration::Days(1), 0),
+            },
+            0,
+        );
+        for _ in 0..3 {
+            assert_eq!(evaluate_function(1, 1, &function, 10, receiver, None), None);
+            commit_function(1, 1, &mut function, 10);
+        }
+        assert_eq!(evaluate_function(1, 1, &function, 20, receiver, None), Some(true));
+        // The window has moved, the previous transfers are not counted anymore
+        assert_eq!(evaluate_function(1, 1, &function, 2 * day, receiver, None), None);
+    }
+
+    #[test]
+    fn transaction_counter_counts_every_transfer_of_a_batch() {
+        let receiver = Pubkey::new_unique();
+        let mut parameters = parameters(
+            vec![TwoAuthFunction::TransactionCounterWithTimeWindow {
+                max: 3,
+                window: CircularTimeWindow::new(crate::Duration::Days(1), 0),
+            }],
+            None,
+        );
+        // A batch of max + 1 transfers needs the two auth, even with an empty window
+        assert!(need_two_auth(4, 4, &parameters, 10, receiver, &[], None));
+        assert!(!need_two_auth(3, 3, &parameters, 10, receiver, &[], None));
+        commit_two_auth(3, 3, &mut parameters, 10);
+        assert!(need_two_auth(1, 1, &parameters, 20, receiver, &[], None));
+    }
+
+    #[test]
+    fn negative_downgrade_delay() {
+        let args = |downgrade_delay| TwoAuthArgs {
+            functions: vec![TwoAuthFunction::Always],
+            rule: None,
+            allowed_issuers: vec![],
+            price_max_age: None,
+            downgrade_delay,
+        };
+        assert!(init_two_auth_parameters(&args(-1), Pubkey::new_unique(), 0).is_err());
+        assert!(init_two_auth_parameters(&args(0), Pubkey::new_unique(), 0).is_ok());
+    }
+
+    #[test]
+    fn zero_duration_window_is_rejected() {
+        let args = |duration| TwoAuthArgs {
+            functions: vec![TwoAuthFunction::TransactionCounterWithTimeWindow {
+                max: 2,
+                window: CircularTimeWindow::new(duration, 0),
+            }],
+            rule: None,
+            allowed_issuers: vec![],
+            price_max_age: None,
+            downgrade_delay: 0,
+        };
+        assert!(init_two_auth_parameters(&args(crate::Duration::Days(0)), Pubkey::new_unique(), 0).is_err());
+        assert!(init_two_auth_parameters(&args(crate::Duration::Days(1)), Pubkey::new_unique(), 0).is_ok());
+    }
+
+    #[test]
+    fn general_white_list() {
+        let receiver = Pubkey::new_unique();
+        let general_white_list = GeneralWhiteList {
+            two_auth_entity: Pubkey::new_unique(),
+            white_list: vec![receiver],
+        };
+        let parameters = parameters(vec![TwoAuthFunction::DeactivateForGeneralWhiteList, TwoAuthFunction::Always], None);
+        assert!(!need_two_auth(1, 1, &parameters, 0, receiver, &[], Some(&general_white_list)));
+        assert!(need_two_auth(1, 1, &parameters, 0, Pubkey::new_unique(), &[], Some(&general_white_list)));
+        assert!(need_two_auth(1, 1, &parameters, 0, receiver, &[], None));
+    }
+
+    #[test]
+    fn two_auth_rule() {
+        let receiver = Pubkey::new_unique();
+        let mut parameters = parameters(
+            vec![],
+            Some(TwoAuthRule {
+                nodes: vec![
+                    TwoAuthRuleNode::Function(TwoAuthFunction::OnMax { max: 10 }),
+                    TwoAuthRuleNode::Function(TwoAuthFunction::DeactivateForUserSpecificWhiteList { white_list: vec![receiver] }),
+                    TwoAuthRuleNode::Not(1),
+                    TwoAuthRuleNode::All(vec![0, 2]),
+                ],
+            }),
+        );
+        assert!(!need_two_auth(5, 1, &parameters, 0, Pubkey::new_unique(), &[], None));
+        assert!(!need_two_auth(20, 1, &parameters, 0, receiver, &[], None));
+        assert_eq!(evaluate_two_auth(20, 1, &parameters, 0, Pubkey::new_unique(), &[], None), Some(TwoAuthFunctionLocation::RuleNode(3)));
+
+        parameters.functions = vec![TwoAuthFunction::Always];
+        assert!(need_two_auth(5, 1, &parameters, 0, receiver, &[], None));
+    }
+
+    #[test]
+    fn simulate_then_commit() {
+        let mut parameters = parameters(
+            vec![
+                TwoAuthFunction::DeactivateForUserSpecificWhiteList { white_list: vec![] },
+                TwoAuthFunction::CounterWithTimeWindow { max: 10, window: CircularTimeWindow::new(crate::Duration::Days(1), 0) },
+            ],
+            Some(TwoAuthRule {
+                nodes: vec![TwoAuthRuleNode::Function(TwoAuthFunction::TransactionCounterWithTimeWindow {
+                    max: 2,
+                    window: CircularTimeWindow::new(crate::Duration::Days(1), 0),
+                })],
+            }),
+        );
+        let receiver = Pubkey::new_unique();
+
+        let simulation = simulate_need_two_auth(4, &parameters, 0, receiver, &[], None);
+        assert!(!simulation.need_two_auth);
+        assert_eq!(simulation.counters.len(), 2);
+        assert_eq!(simulation.counters[0].location, TwoAuthFunctionLocation::Function(1));
+        assert_eq!(simulation.counters[0].remaining, 9);
+        assert_eq!(simulation.counters[1].location, TwoAuthFunctionLocation::RuleNode(0));
+        assert_eq!(simulation.counters[1].remaining, 2);
+
+        commit_two_auth(4, 1, &mut parameters, 0);
+        let simulation = simulate_need_two_auth(6, &parameters, 0, receiver, &[], None);
+        assert!(simulation.need_two_auth);
+        assert_eq!(simulation.triggered_by, Some(TwoAuthFunctionLocation::Function(1)));
+        assert_eq!(simulation.counters[0].remaining, 5);
+        assert_eq!(simulation.counters[1].remaining, 1);
+    }
+
+    #[test]
+    fn two_auth_amount_in_reference_currency() {
+        let mut parameters = parameters(vec![TwoAuthFunction::OnMax { max: 1000 }], None);
+        let price_account = PriceAccount {
+            wrapper_account: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            price_authority: Pubkey::new_unique(),
+            price: 150, // 1.50 in cents
+            decimals: 6,
+            last_update: 100,
+        };
+        assert_eq!(get_two_auth_amount(5_000_000, &parameters, None, 100).unwrap(), 5_000_000);
+
+        parameters.price_max_age = Some(60);
+        assert_eq!(get_two_auth_amount(5_000_000, &parameters, Some(&price_account), 100).unwrap(), 750);
+        assert!(get_two_auth_amount(5_000_000, &parameters, None, 100).is_err());
+        assert!(get_two_auth_amount(5_000_000, &parameters, Some(&price_account), 161).is_err());
+    }
+
+    #[test]
+    fn counter_reset_on_max_commit() {
+        let receiver = Pubkey::new_unique();
+        let mut function = TwoAuthFunction::CounterResetOnMax { max: 10, counter: 0 };
+        assert_eq!(evaluate_function(6, 1, &function, 0, receiver, None), None);
+        commit_function(6, 1, &mut function, 0);
+        // 6 + 6 reaches max: the two auth is needed
+        assert_eq!(evaluate_function(6, 1, &function, 0, receiver, None), Some(true));
+        // Once approved, the counter is reset
+        commit_function(6, 1, &mut function, 0);
+        assert_eq!(function.get_counter(0), Some((0, 10)));
+    }
+
+    #[test]
+    fn counter_reset_on_time_commit() {
+        let day = 86400;
+        let receiver = Pubkey::new_unique();
+        let mut function = init_functions(
+            &TwoAuthFunction::CounterResetOnTime { max: 10, duration: crate::Duration::Days(1), counter: 0, last_reset_time: 0 },
+            0,
+        );
+        commit_function(6, 1, &mut function, 0);
+        // The duration is a day, not a second
+        assert_eq!(evaluate_function(6, 1, &function, 100, receiver, None), Some(true));
+        assert_eq!(evaluate_function(6, 1, &function, 2 * day, receiver, None), None);
+        commit_function(6, 1, &mut function, 2 * day);
+        assert_eq!(function.get_counter(2 * day), Some((6, 10)));
+    }
+
+    #[test]
+    fn allowed_issuers() {
+        let trusted_issuer = Pubkey::new_unique();
+        let issuer = |key: Pubkey, active: bool, expires_at: i64| Issuer { key, last_modified: 0, expires_at, active };
+        let mut parameters = parameters(vec![], None);
+        let receiver = Pubkey::new_unique();
+        assert!(!need_two_auth(1, 1, &parameters, 0, receiver, &[], None));
+
+        parameters.allowed_issuers = vec![trusted_issuer];
+        assert!(!need_two_auth(1, 1, &parameters, 0, receiver, &[issuer(trusted_issuer, true, 100)], None));
+        assert_eq!(
+            evaluate_two_auth(1, 1, &parameters, 0, receiver, &[issuer(Pubkey::new_unique(), true, 100)], None),
+            Some(TwoAuthFunctionLocation::AllowedIssuers)
+        );
+        // Expired or deactivated attestations don't count
+        assert!(need_two_auth(1, 1, &parameters, 100, receiver, &[issuer(trusted_issuer, true, 100)], None));
+        assert!(need_two_auth(1, 1, &parameters, 0, receiver, &[issuer(trusted_issuer, false, 100)], None));
+    }
+}
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::{GeneralWhiteListAddressAdded, GeneralWhiteListAddressRemoved, GeneralWhiteListInitialized},
+    GeneralWhiteList,
+};
+
+#[derive(Accounts)]
+#[instruction(white_list: Vec<Pubkey>)]
+pub struct InitializeGeneralWhiteList<'info> {
+    #[account(init, seeds=[b"white_list", two_auth_entity.key().as_ref()], bump, payer=payer, space=GeneralWhiteList::get_init_len(&white_list))]
+    pub general_white_list: Account<'info, GeneralWhiteList>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub two_auth_entity: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddToGeneralWhiteList<'info> {
+    #[account(mut, seeds=[b"white_list", two_auth_entity.key().as_ref()], bump, has_one=two_auth_entity, realloc=general_white_list.get_add_address_len(), realloc::payer=payer, realloc::zero=false)]
+    pub general_white_list: Account<'info, GeneralWhiteList>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub two_auth_entity: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromGeneralWhiteList<'info> {
+    #[account(mut, seeds=[b"white_list", two_auth_entity.key().as_ref()], bump, has_one=two_auth_entity, realloc=general_white_list.get_remove_address_len(), realloc::payer=payer, realloc::zero=true)]
+    pub general_white_list: Account<'info, GeneralWhiteList>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub two_auth_entity: Signer<'info>,
+    /// CHECK: The address to be removed from the white list
+    #[account(constraint = general_white_list.white_list.contains(&address.key()))]
+    pub address: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn _initialize_general_white_list(
+    ctx: Context<InitializeGeneralWhiteList>,
+    white_list: Vec<Pubkey>,
+) -> Result<()> {
+    let general_white_list = &mut ctx.accounts.general_white_list;
+    general_white_list.two_auth_entity = ctx.accounts.two_auth_entity.key();
+    general_white_list.white_list = white_list;
+
+    emit!(GeneralWhiteListInitialized {
+        general_white_list: general_white_list.key(),
+        two_auth_entity: general_white_list.two_auth_entity,
+        white_list: general_white_list.white_list.clone(),
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn _add_to_general_white_list(ctx: Context<AddToGeneralWhiteList>, address: Pubkey) -> Result<()> {
+    let general_white_list = &mut ctx.accounts.general_white_list;
+    general_white_list.white_list.push(address);
+
+    emit!(GeneralWhiteListAddressAdded {
+        general_white_list: general_white_list.key(),
+        address,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn _remove_from_general_white_list(ctx: Context<RemoveFromGeneralWhiteList>) -> Result<()> {
+    let general_white_list = &mut ctx.accounts.general_white_list;
+    let index = general_white_list
+        .white_list
+        .iter()
+        .position(|x| *x == ctx.accounts.address.key())
+        .unwrap();
+    general_white_list.white_list.remove(index);
+
+    emit!(GeneralWhiteListAddressRemoved {
+        general_white_list: general_white_list.key(),
+        address: ctx.accounts.address.key(),
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
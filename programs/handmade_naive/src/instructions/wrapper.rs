@@ -5,7 +5,12 @@ use anchor_spl::{
     token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::{error::WrapperError, WrappedTokenAccount, WrapperAccount};
+use crate::{
+    check_idendities, check_idendity_not_recovered, check_two_auth,
+    error::{TransferError, WrapperError},
+    events::{AllowUnverifiedRecipientsSet, RequireMemoSet, TokensDeposited, TokensUnwrapped, TransferOutEvent, TokensWrapped, WrappedAccountInitialized, WrapperInitialized, WrapperIssuerAdded, WrapperIssuerRemoved},
+    blocklist, limits, two_auth, TransferControls, GeneralWhiteList, IdAccount, LimitCounter, PriceAccount, TravelRuleRecord, Treasury, TwoAuth, WrappedTokenAccount, WrapperAccount,
+};
 
 #[derive(Accounts)]
 #[instruction(list_issuer: Vec<Pubkey>)]
@@ -58,6 +63,20 @@ pub struct InitializeWrappedAccount<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct SetAllowUnverifiedRecipients<'info> {
+    #[account(mut, seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRequireMemo<'info> {
+    #[account(mut, has_one=owner)]
+    pub wrapped_token_account: Account<'info, WrappedTokenAccount>,
+    pub owner: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct WrapTokenHolder<'info> {
     #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
@@ -69,6 +88,8 @@ pub struct WrapTokenHolder<'info> {
         associated_token::authority = wrapper_account
     )]
     pub wrapper_associated_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(init, seeds=[b"treasury", wrapper_account.key().as_ref(), mint.key().as_ref()], bump, payer=payer, space=Treasury::LEN)]
+    pub treasury: Account<'info, Treasury>,
     #[account(mut)]
     pub payer: Signer<'info>,
     /// CHECK: The approver of the wrapper
@@ -98,24 +119,161 @@ pub struct WrapTokens<'info> {
     pub user_token_account: InterfaceAccount<'info, TokenAccount>,
     #[account(constraint = owner.key() == user_wrapped_token_account.owner)]
     pub owner: Signer<'info>,
+    /// CHECK: Has to be empty, the owner is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), owner.key().as_ref()], bump)]
+    pub blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"treasury", wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub treasury: Account<'info, Treasury>,
     #[account(mint::token_program = token_program)]
     pub mint: InterfaceAccount<'info, Mint>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct DepositFor<'info> {
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Box<Account<'info, WrapperAccount>>,
+    /// CHECK: The approver of the wrapper
+    pub approver: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = wrapper_account,
+    )]
+    pub wrapper_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, seeds=[b"wrapped_token", wrapper_account.key().as_ref(), mint.key().as_ref(), recipient_wrapped_account.owner.as_ref()], bump, has_one=wrapper_account, has_one = mint)]
+    pub recipient_wrapped_account: Box<Account<'info, WrappedTokenAccount>>,
+    #[account(seeds = [b"identity", recipient_wrapped_account.owner.as_ref()], bump)]
+    pub idendity_recipient: Box<Account<'info, IdAccount>>,
+    /// CHECK: Has to be empty, the recipient is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), recipient_wrapped_account.owner.as_ref()], bump)]
+    pub recipient_blocklist_entry: UncheckedAccount<'info>,
+    /// CHECK: Has to be empty, the payer is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), payer.key().as_ref()], bump)]
+    pub payer_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, token::authority = payer, token::mint = mint)]
+    pub payer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub payer: Signer<'info>,
+    #[account(mut, seeds=[b"treasury", wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub treasury: Box<Account<'info, Treasury>>,
+    #[account(mint::token_program = token_program)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct TransferOut<'info> {
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Box<Account<'info, WrapperAccount>>,
+    /// CHECK: The approver of the wrapper
+    pub approver: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = wrapper_account,
+    )]
+    pub wrapper_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, seeds=[b"wrapped_token", wrapper_account.key().as_ref(), mint.key().as_ref(), source_owner.key().as_ref()], bump, has_one=wrapper_account, has_one = mint)]
+    pub source_wrapped_account: Box<Account<'info, WrappedTokenAccount>>,
+    #[account(mut, constraint = source_owner.key() == source_wrapped_account.owner)]
+    pub source_owner: Signer<'info>,
+    #[account(seeds = [b"identity", source_owner.key().as_ref()], bump)]
+    pub idendity_sender: Box<Account<'info, IdAccount>>,
+    /// CHECK: Has to be empty, the sender is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub sender_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, token::mint = mint)]
+    pub destination_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(seeds = [b"identity", destination_token_account.owner.as_ref()], bump)]
+    pub idendity_receiver: Option<Box<Account<'info, IdAccount>>>, // Needed unless the wrapper allows unverified recipients
+    /// CHECK: Has to be empty, the owner of the destination token account is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), destination_token_account.owner.as_ref()], bump)]
+    pub receiver_blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), mint.key().as_ref(), source_owner.key().as_ref()], bump)]
+    pub two_auth: Box<Account<'info,TwoAuth>>,
+    pub two_auth_signer: Option<Signer<'info>>,
+    pub general_white_list: Option<Box<Account<'info, GeneralWhiteList>>>,
+    #[account(seeds=[b"price", wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub price_account: Option<Box<Account<'info, PriceAccount>>>,
+    #[account(mut, seeds=[b"limit_counter", source_wrapped_account.key().as_ref()], bump)]
+    pub limit_counter: Option<Box<Account<'info, LimitCounter>>>, // Needed when the wrapper has baseline limits
+    #[account(mut, close = source_owner)]
+    pub travel_rule_record: Option<Box<Account<'info, TravelRuleRecord>>>, // Needed from the travel rule threshold of the wrapper, closed by the transfer
+    #[account(mut, seeds=[b"treasury", wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub treasury: Box<Account<'info, Treasury>>,
+    #[account(mint::token_program = token_program)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct UnwrapTokens<'info> {
+    #[account(seeds=[b"wrapper", approver.key().as_ref()], bump)]
+    pub wrapper_account: Account<'info, WrapperAccount>,
+    /// CHECK: The approver of the wrapper
+    pub approver: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = wrapper_account,
+    )]
+    pub wrapper_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds=[b"wrapped_token", wrapper_account.key().as_ref(), mint.key().as_ref(), owner.key().as_ref()], bump, has_one=wrapper_account, has_one = mint)]
+    pub user_wrapped_token_account: Account<'info, WrappedTokenAccount>,
+    #[account(mut, token::authority = owner, token::mint = mint)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = owner.key() == user_wrapped_token_account.owner)]
+    pub owner: Signer<'info>,
+    #[account(seeds = [b"identity", owner.key().as_ref()], bump)]
+    pub idendity: Account<'info, IdAccount>,
+    /// CHECK: Has to be empty, the owner is blocked otherwise
+    #[account(seeds=[b"blocklist", wrapper_account.key().as_ref(), owner.key().as_ref()], bump)]
+    pub blocklist_entry: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"two_auth", wrapper_account.key().as_ref(), mint.key().as_ref(), owner.key().as_ref()], bump)]
+    pub two_auth: Account<'info,TwoAuth>,
+    pub two_auth_signer: Option<Signer<'info>>,
+    pub general_white_list: Option<Account<'info, GeneralWhiteList>>,
+    #[account(seeds=[b"price", wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub price_account: Option<Account<'info, PriceAccount>>,
+    #[account(mut, seeds=[b"limit_counter", user_wrapped_token_account.key().as_ref()], bump)]
+    pub limit_counter: Option<Account<'info, LimitCounter>>, // Needed when the wrapper has baseline limits
+    #[account(mut, seeds=[b"treasury", wrapper_account.key().as_ref(), mint.key().as_ref()], bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
 pub fn _initialize_wrapper(
     ctx: Context<InitializeWrapper>,
     list_issuer: Vec<Pubkey>,
 ) -> Result<()> {
     let wrapper_account = &mut ctx.accounts.wrapper_account;
     wrapper_account.list_issuer = list_issuer;
+    wrapper_account.fee_schedule = None;
+    wrapper_account.allow_unverified_recipients = false;
+    wrapper_account.baseline_limits = None;
+    wrapper_account.travel_rule_threshold = None;
+
+    emit!(WrapperInitialized {
+        wrapper_account: wrapper_account.key(),
+        approver: ctx.accounts.approver.key(),
+        list_issuer: wrapper_account.list_issuer.clone(),
+        time: Clock::get()?.unix_timestamp,
+    });
     Ok(())
 }
 
 pub fn _add_issuers_wrapper(ctx: Context<AddWrapperIssuer>, issuer: Pubkey) -> Result<()> {
     let wrapper_account = &mut ctx.accounts.wrapper_account;
     wrapper_account.list_issuer.push(issuer);
+
+    emit!(WrapperIssuerAdded {
+        wrapper_account: wrapper_account.key(),
+        issuer,
+        time: Clock::get()?.unix_timestamp,
+    });
     Ok(())
 }
 
@@ -127,10 +285,23 @@ pub fn _remove_issuer_wrapper(ctx: Context<DeleteWrapperIssuer>) -> Result<()> {
         .position(|x| *x == ctx.accounts.issuer.key())
         .unwrap();
     wrapper_account.list_issuer.remove(index);
+
+    emit!(WrapperIssuerRemoved {
+        wrapper_account: wrapper_account.key(),
+        issuer: ctx.accounts.issuer.key(),
+        time: Clock::get()?.unix_timestamp,
+    });
     Ok(())
 }
 
-pub fn _initialize_mint(_ctx: Context<WrapTokenHolder>) -> Result<()> {
+pub fn _initialize_mint(ctx: Context<WrapTokenHolder>) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.wrapper_account = ctx.accounts.wrapper_account.key();
+    treasury.mint = ctx.accounts.mint.key();
+    treasury.wrap_fee_basis_points = 0;
+    treasury.unwrap_fee_basis_points = 0;
+    treasury.user_supply = 0;
+    treasury.collected_fees = 0;
     Ok(())
 }
 
@@ -141,6 +312,38 @@ pub fn _initialize_wrap_account(ctx: Context<InitializeWrappedAccount>) -> Resul
     wrapped_token_account.wrapper_account = ctx.accounts.wrapper_account.key();
     wrapped_token_account.mint = ctx.accounts.mint.key();
     wrapped_token_account.last_tx = Clock::get()?.unix_timestamp;
+    wrapped_token_account.require_memo = false;
+
+    emit!(WrappedAccountInitialized {
+        wrapped_token_account: wrapped_token_account.key(),
+        wrapper_account: wrapped_token_account.wrapper_account,
+        mint: wrapped_token_account.mint,
+        owner: wrapped_token_account.owner,
+        time: wrapped_token_account.last_tx,
+    });
+    Ok(())
+}
+
+pub fn _set_allow_unverified_recipients(ctx: Context<SetAllowUnverifiedRecipients>, allow_unverified_recipients: bool) -> Result<()> {
+    let wrapper_account = &mut ctx.accounts.wrapper_account;
+    wrapper_account.allow_unverified_recipients = allow_unverified_recipients;
+
+    emit!(AllowUnverifiedRecipientsSet {
+        wrapper_account: wrapper_account.key(),
+        allow_unverified_recipients,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn _set_require_memo(ctx: Context<SetRequireMemo>, require_memo: bool) -> Result<()> {
+    ctx.accounts.wrapped_token_account.require_memo = require_memo;
+
+    emit!(RequireMemoSet {
+        wrapped_token_account: ctx.accounts.wrapped_token_account.key(),
+        require_memo,
+        time: Clock::get()?.unix_timestamp,
+    });
     Ok(())
 }
 
@@ -150,6 +353,7 @@ pub fn _wrap_tokens(ctx: Context<WrapTokens>, amount: u64, decimals: u8) -> Resu
     if mint.decimals != decimals {
         return Err(WrapperError::InvalidDecimals.into());
     }
+    blocklist::check_not_blocked(&ctx.accounts.blocklist_entry)?;
 
     // CPI to transfer tokens from user to wrapper
     let ix = spl_token::instruction::transfer(
@@ -170,9 +374,248 @@ pub fn _wrap_tokens(ctx: Context<WrapTokens>, amount: u64, decimals: u8) -> Resu
         ],
     )?;
 
+    // The entry fee stays in the wrapper token account but belongs to the treasury
+    let treasury = &mut ctx.accounts.treasury;
+    let fee = treasury.get_wrap_fee(amount);
+    let wrapped_amount = amount - fee;
+    treasury.collected_fees = treasury.collected_fees.checked_add(fee).ok_or(TransferError::Overflow)?;
+    treasury.user_supply = treasury.user_supply.checked_add(wrapped_amount).ok_or(TransferError::Overflow)?;
+
     // Adding to the corresponding wrapped token account
     let wrapped_token_account = &mut ctx.accounts.user_wrapped_token_account;
-    wrapped_token_account.amount += amount;
+    wrapped_token_account.amount += wrapped_amount;
+
+    emit!(TokensWrapped {
+        wrapped_token_account: wrapped_token_account.key(),
+        wrapper_account: ctx.accounts.wrapper_account.key(),
+        mint: mint.key(),
+        owner: ctx.accounts.owner.key(),
+        amount: wrapped_amount,
+        fee,
+        time: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/*
+    Same as wrap_tokens, but any payer funds the wrapped account of a recipient with a valid identity
+*/
+pub fn _deposit_for(ctx: Context<DepositFor>, amount: u64, decimals: u8) -> Result<()> {
+    let mint = &ctx.accounts.mint;
+
+    if mint.decimals != decimals {
+        return Err(WrapperError::InvalidDecimals.into());
+    }
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let idendity_recipient = &ctx.accounts.idendity_recipient;
+    check_idendity_not_recovered(idendity_recipient)?;
+    blocklist::check_not_blocked(&ctx.accounts.recipient_blocklist_entry)?;
+    blocklist::check_not_blocked(&ctx.accounts.payer_blocklist_entry)?;
+    check_idendities(&idendity_recipient.issuers, &ctx.accounts.wrapper_account.list_issuer, current_time)?;
+
+    // CPI to transfer tokens from payer to wrapper
+    let ix = spl_token::instruction::transfer(
+        ctx.accounts.token_program.key,
+        &ctx.accounts.payer_token_account.key(),
+        &ctx.accounts.wrapper_token_account.key(),
+        ctx.accounts.payer.key,
+        &[ctx.accounts.payer.key],
+        amount,
+    )?;
+    program::invoke(
+        &ix,
+        &[
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.payer_token_account.to_account_info(),
+            ctx.accounts.wrapper_token_account.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+        ],
+    )?;
+
+    let treasury = &mut ctx.accounts.treasury;
+    let fee = treasury.get_wrap_fee(amount);
+    let wrapped_amount = amount - fee;
+    treasury.collected_fees = treasury.collected_fees.checked_add(fee).ok_or(TransferError::Overflow)?;
+    treasury.user_supply = treasury.user_supply.checked_add(wrapped_amount).ok_or(TransferError::Overflow)?;
+
+    let wrapped_token_account = &mut ctx.accounts.recipient_wrapped_account;
+    wrapped_token_account.amount = wrapped_token_account.amount.checked_add(wrapped_amount).ok_or(TransferError::Overflow)?;
+
+    emit!(TokensDeposited {
+        wrapped_token_account: wrapped_token_account.key(),
+        wrapper_account: ctx.accounts.wrapper_account.key(),
+        mint: mint.key(),
+        payer: ctx.accounts.payer.key(),
+        amount: wrapped_amount,
+        fee,
+        time: current_time,
+    });
+    Ok(())
+}
+
+/*
+    Unwrapping takes the tokens out of the wrapper: the two auth is checked as for a transfer to the owner's own identity
+    The exit fee is kept by the treasury, the owner receives amount - fee tokens
+*/
+pub fn _unwrap_tokens(ctx: Context<UnwrapTokens>, amount: u64, decimals: u8) -> Result<()> {
+    if ctx.accounts.mint.decimals != decimals {
+        return Err(WrapperError::InvalidDecimals.into());
+    }
+
+    let idendity = &ctx.accounts.idendity;
+    check_idendity_not_recovered(idendity)?;
+    blocklist::check_not_blocked(&ctx.accounts.blocklist_entry)?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let two_auth = &mut ctx.accounts.two_auth.two_auth;
+    let mut two_auth_amount = amount;
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth_amount = two_auth::get_two_auth_amount(amount, two_auth_parameters, ctx.accounts.price_account.as_deref(), current_time)?;
+    }
+    let general_white_list = ctx.accounts.general_white_list.as_deref();
+    check_two_auth(two_auth, &ctx.accounts.two_auth_signer, two_auth_amount, 1, current_time, idendity.key(), &idendity.issuers, general_white_list)?;
+
+    limits::check_baseline_limits(&ctx.accounts.wrapper_account, ctx.accounts.limit_counter.as_deref_mut(), amount, 1, current_time)?;
+
+    let wrapped_token_account = &mut ctx.accounts.user_wrapped_token_account;
+    wrapped_token_account.amount = wrapped_token_account.amount.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+    wrapped_token_account.last_tx = current_time;
+
+    let treasury = &mut ctx.accounts.treasury;
+    let fee = treasury.get_unwrap_fee(amount);
+    treasury.user_supply = treasury.user_supply.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+    treasury.collected_fees = treasury.collected_fees.checked_add(fee).ok_or(TransferError::Overflow)?;
+
+    transfer_from_wrapper(
+        &ctx.accounts.token_program,
+        &ctx.accounts.wrapper_token_account,
+        &ctx.accounts.user_token_account,
+        &ctx.accounts.wrapper_account,
+        &ctx.accounts.approver.key(),
+        ctx.bumps.wrapper_account,
+        amount - fee,
+    )?;
+
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth::commit_two_auth(two_auth_amount, 1, two_auth_parameters, current_time);
+    }
+
+    emit!(TokensUnwrapped {
+        wrapped_token_account: wrapped_token_account.key(),
+        wrapper_account: ctx.accounts.wrapper_account.key(),
+        mint: ctx.accounts.mint.key(),
+        owner: ctx.accounts.owner.key(),
+        amount,
+        fee,
+        time: current_time,
+    });
+    Ok(())
+}
+
+/*
+    Unwraps and pays any token account of the mint in one step, the exit fee of unwrap applies
+    Without identity, the recipient is the token account itself for the two auth (white lists) and has no issuer
+*/
+pub fn _transfer_out(ctx: Context<TransferOut>, amount: u64) -> Result<()> {
+    let idendity_sender = &ctx.accounts.idendity_sender;
+    check_idendity_not_recovered(idendity_sender)?;
+    let controls = TransferControls {
+        sender_blocklist_entry: &ctx.accounts.sender_blocklist_entry,
+        receiver_blocklist_entry: &ctx.accounts.receiver_blocklist_entry,
+        limit_counter: ctx.accounts.limit_counter.as_deref_mut().map(|c| &mut **c),
+        travel_rule_record: ctx.accounts.travel_rule_record.as_deref().map(|r| &**r),
+    };
+    controls.check_not_blocked()?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let wrapper_account = &ctx.accounts.wrapper_account;
+    check_idendities(&idendity_sender.issuers, &wrapper_account.list_issuer, current_time)?;
+
+    let (receiver, receiver_issuers) = match &ctx.accounts.idendity_receiver {
+        Some(idendity_receiver) => {
+            check_idendity_not_recovered(idendity_receiver)?;
+            check_idendities(&idendity_receiver.issuers, &wrapper_account.list_issuer, current_time)?;
+            (idendity_receiver.key(), idendity_receiver.issuers.as_slice())
+        }
+        None if wrapper_account.allow_unverified_recipients => (ctx.accounts.destination_token_account.key(), &[][..]),
+        None => return Err(WrapperError::UnverifiedRecipient.into()),
+    };
+
+    let two_auth = &mut ctx.accounts.two_auth.two_auth;
+    let mut two_auth_amount = amount;
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth_amount = two_auth::get_two_auth_amount(amount, two_auth_parameters, ctx.accounts.price_account.as_deref().map(|p| &**p), current_time)?;
+    }
+    let general_white_list = ctx.accounts.general_white_list.as_deref().map(|g| &**g);
+    check_two_auth(two_auth, &ctx.accounts.two_auth_signer, two_auth_amount, 1, current_time, receiver, receiver_issuers, general_white_list)?;
+
+    controls.check_limits(wrapper_account, &ctx.accounts.source_wrapped_account.key(), &ctx.accounts.destination_token_account.key(), amount, current_time)?;
 
+    let source = &mut ctx.accounts.source_wrapped_account;
+    source.amount = source.amount.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+    source.last_tx = current_time;
+
+    let treasury = &mut ctx.accounts.treasury;
+    let fee = treasury.get_unwrap_fee(amount);
+    treasury.user_supply = treasury.user_supply.checked_sub(amount).ok_or(TransferError::InsufficientFunds)?;
+    treasury.collected_fees = treasury.collected_fees.checked_add(fee).ok_or(TransferError::Overflow)?;
+
+    transfer_from_wrapper(
+        &ctx.accounts.token_program,
+        &ctx.accounts.wrapper_token_account,
+        &ctx.accounts.destination_token_account,
+        wrapper_account,
+        &ctx.accounts.approver.key(),
+        ctx.bumps.wrapper_account,
+        amount - fee,
+    )?;
+
+    if let Some(two_auth_parameters) = two_auth {
+        two_auth::commit_two_auth(two_auth_amount, 1, two_auth_parameters, current_time);
+    }
+
+    emit!(TransferOutEvent {
+        source_wrapped_account: source.key(),
+        destination_token_account: ctx.accounts.destination_token_account.key(),
+        amount,
+        fee,
+        verified_recipient: ctx.accounts.idendity_receiver.is_some(),
+        time: current_time,
+    });
+    Ok(())
+}
+
+/*
+    CPI to transfer tokens out of the wrapper token account, signed by the wrapper
+*/
+pub fn transfer_from_wrapper<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    wrapper_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination_token_account: &InterfaceAccount<'info, TokenAccount>,
+    wrapper_account: &Account<'info, WrapperAccount>,
+    approver: &Pubkey,
+    wrapper_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let ix = spl_token::instruction::transfer(
+        token_program.key,
+        &wrapper_token_account.key(),
+        &destination_token_account.key(),
+        &wrapper_account.key(),
+        &[&wrapper_account.key()],
+        amount,
+    )?;
+    let seeds: &[&[&[u8]]] = &[&[b"wrapper", approver.as_ref(), &[wrapper_bump]]];
+    program::invoke_signed(
+        &ix,
+        &[
+            token_program.to_account_info(),
+            wrapper_token_account.to_account_info(),
+            destination_token_account.to_account_info(),
+            wrapper_account.to_account_info(),
+        ],
+        seeds,
+    )?;
     Ok(())
 }
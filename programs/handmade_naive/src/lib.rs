@@ -1,11 +1,12 @@
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod instructions;
 pub mod state;
 
 use anchor_lang::prelude::*;
 
-// pub use constants::*;
+pub use constants::*;
 pub use instructions::*;
 pub use state::*;
 
@@ -44,16 +45,86 @@ pub mod handmade_naive {
         wrapper::_wrap_tokens(ctx, amount, decimals)
     }
 
-    // TODO: Unwrap tokens
+    pub fn set_fee_schedule(
+        ctx: Context<SetFeeSchedule>,
+        fee_schedule: Option<FeeSchedule>,
+    ) -> Result<()> {
+        fees::_set_fee_schedule(ctx, fee_schedule)
+    }
+
+    pub fn initialize_fee_collector(ctx: Context<InitializeFeeCollector>) -> Result<()> {
+        fees::_initialize_fee_collector(ctx)
+    }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        fees::_withdraw_fees(ctx, amount)
+    }
+
+    pub fn set_require_memo(ctx: Context<SetRequireMemo>, require_memo: bool) -> Result<()> {
+        wrapper::_set_require_memo(ctx, require_memo)
+    }
+
+    pub fn unwrap_tokens(ctx: Context<UnwrapTokens>, amount: u64, decimals: u8) -> Result<()> {
+        wrapper::_unwrap_tokens(ctx, amount, decimals)
+    }
+
+    pub fn deposit_for(ctx: Context<DepositFor>, amount: u64, decimals: u8) -> Result<()> {
+        wrapper::_deposit_for(ctx, amount, decimals)
+    }
+
+    pub fn transfer_out(ctx: Context<TransferOut>, amount: u64) -> Result<()> {
+        wrapper::_transfer_out(ctx, amount)
+    }
+
+    pub fn set_allow_unverified_recipients(
+        ctx: Context<SetAllowUnverifiedRecipients>,
+        allow_unverified_recipients: bool,
+    ) -> Result<()> {
+        wrapper::_set_allow_unverified_recipients(ctx, allow_unverified_recipients)
+    }
+
+    pub fn set_wrap_fees(
+        ctx: Context<SetWrapFees>,
+        wrap_fee_basis_points: u16,
+        unwrap_fee_basis_points: u16,
+    ) -> Result<()> {
+        fees::_set_wrap_fees(ctx, wrap_fee_basis_points, unwrap_fee_basis_points)
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        fees::_withdraw_treasury(ctx, amount)
+    }
+
+    // Migration instructions
+
+    pub fn migrate_wrapper(ctx: Context<MigrateWrapper>) -> Result<()> {
+        migration::_migrate_wrapper(ctx)
+    }
+
+    pub fn migrate_wrapped_account(ctx: Context<MigrateWrappedAccount>) -> Result<()> {
+        migration::_migrate_wrapped_account(ctx)
+    }
+
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        migration::_initialize_treasury(ctx)
+    }
 
     // Idendity instructions
 
-    pub fn initialize_id(ctx: Context<InitializeId>, id_validity_duration: i64) -> Result<()> {
-        idendity::_initialize_id(ctx, id_validity_duration)
+    pub fn initialize_id(
+        ctx: Context<InitializeId>,
+        id_validity_duration: i64,
+        nullifier: Option<[u8; 32]>,
+    ) -> Result<()> {
+        idendity::_initialize_id(ctx, id_validity_duration, nullifier)
     }
 
-    pub fn add_issuer_to_id(ctx: Context<AddIssuer>, id_validity_duration: i64) -> Result<()> {
-        idendity::_add_issuer_to_id(ctx, id_validity_duration)
+    pub fn add_issuer_to_id(
+        ctx: Context<AddIssuer>,
+        id_validity_duration: i64,
+        nullifier: Option<[u8; 32]>,
+    ) -> Result<()> {
+        idendity::_add_issuer_to_id(ctx, id_validity_duration, nullifier)
     }
 
     // TwoAuth instructions
@@ -72,12 +143,205 @@ pub mod handmade_naive {
         two_auth::_update_two_auth(ctx, two_auth)
     }
 
+    pub fn schedule_two_auth_update(
+        ctx: Context<ScheduleTwoAuthUpdate>,
+        two_auth: Option<TwoAuthArgs>,
+    ) -> Result<()> {
+        two_auth::_schedule_two_auth_update(ctx, two_auth)
+    }
+
+    pub fn cancel_two_auth_update(ctx: Context<CancelTwoAuthUpdate>) -> Result<()> {
+        two_auth::_cancel_two_auth_update(ctx)
+    }
+
+    pub fn apply_two_auth_update(ctx: Context<ApplyTwoAuthUpdate>) -> Result<()> {
+        two_auth::_apply_two_auth_update(ctx)
+    }
+
+    pub fn tighten_two_auth(ctx: Context<TightenTwoAuth>, rule: TwoAuthRule) -> Result<()> {
+        two_auth::_tighten_two_auth(ctx, rule)
+    }
+
+    pub fn simulate_two_auth(
+        ctx: Context<SimulateTwoAuth>,
+        amount: u64,
+        receiver: Pubkey,
+    ) -> Result<TwoAuthSimulation> {
+        two_auth::_simulate_two_auth(ctx, amount, receiver)
+    }
+
+    pub fn initialize_general_white_list(
+        ctx: Context<InitializeGeneralWhiteList>,
+        white_list: Vec<Pubkey>,
+    ) -> Result<()> {
+        white_list::_initialize_general_white_list(ctx, white_list)
+    }
+
+    pub fn add_to_general_white_list(
+        ctx: Context<AddToGeneralWhiteList>,
+        address: Pubkey,
+    ) -> Result<()> {
+        white_list::_add_to_general_white_list(ctx, address)
+    }
+
+    pub fn remove_from_general_white_list(ctx: Context<RemoveFromGeneralWhiteList>) -> Result<()> {
+        white_list::_remove_from_general_white_list(ctx)
+    }
+
+    // Price instructions
+
+    pub fn initialize_price(
+        ctx: Context<InitializePrice>,
+        price_authority: Pubkey,
+        price: u64,
+    ) -> Result<()> {
+        price::_initialize_price(ctx, price_authority, price)
+    }
+
+    pub fn update_price(ctx: Context<UpdatePrice>, price: u64) -> Result<()> {
+        price::_update_price(ctx, price)
+    }
+
     // Recovery instructions
 
+    // Allowance instructions
+
+    pub fn approve_delegate(
+        ctx: Context<ApproveDelegate>,
+        delegate: Pubkey,
+        amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        allowance::_approve_delegate(ctx, delegate, amount, expires_at)
+    }
+
+    pub fn revoke_delegate(ctx: Context<RevokeDelegate>) -> Result<()> {
+        allowance::_revoke_delegate(ctx)
+    }
+
     // Transfer instructions
 
-    pub fn transfer(ctx: Context<Transfer>, amount: u64) -> Result<()> {
-        transfer::_transfer(ctx, amount)
+    pub fn transfer(ctx: Context<Transfer>, amount: u64, memo: Option<String>) -> Result<()> {
+        transfer::_transfer(ctx, amount, memo)
+    }
+
+    pub fn transfer_from(ctx: Context<TransferFrom>, amount: u64, memo: Option<String>) -> Result<()> {
+        transfer::_transfer_from(ctx, amount, memo)
+    }
+
+    pub fn transfer_and_create(ctx: Context<TransferAndCreate>, amount: u64, memo: Option<String>) -> Result<()> {
+        transfer::_transfer_and_create(ctx, amount, memo)
+    }
+
+    pub fn batch_transfer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchTransfer<'info>>,
+        amounts: Vec<u64>,
+        memo: Option<String>,
+    ) -> Result<()> {
+        transfer::_batch_transfer(ctx, amounts, memo)
+    }
+
+    // Scheduled transfer instructions
+
+    pub fn create_scheduled_transfer(
+        ctx: Context<CreateScheduledTransfer>,
+        _id: u64,
+        amount: u64,
+        interval: i64,
+        first_execution: i64,
+        count: u32,
+        memo: Option<String>,
+    ) -> Result<()> {
+        scheduled_transfer::_create_scheduled_transfer(ctx, amount, interval, first_execution, count, memo)
+    }
+
+    pub fn cancel_scheduled_transfer(ctx: Context<CancelScheduledTransfer>) -> Result<()> {
+        scheduled_transfer::_cancel_scheduled_transfer(ctx)
+    }
+
+    pub fn execute_scheduled_transfer(ctx: Context<ExecuteScheduledTransfer>) -> Result<()> {
+        scheduled_transfer::_execute_scheduled_transfer(ctx)
+    }
+
+    // Escrow instructions
+
+    pub fn create_escrow(
+        ctx: Context<CreateEscrow>,
+        _id: u64,
+        amount: u64,
+        arbiter: Option<Pubkey>,
+        deadline: i64,
+    ) -> Result<()> {
+        escrow::_create_escrow(ctx, amount, arbiter, deadline)
+    }
+
+    pub fn release_escrow(ctx: Context<ReleaseEscrow>) -> Result<()> {
+        escrow::_release_escrow(ctx)
+    }
+
+    pub fn refund_escrow(ctx: Context<RefundEscrow>) -> Result<()> {
+        escrow::_refund_escrow(ctx)
+    }
+
+    // Cross wrapper instructions
+
+    pub fn create_trust_agreement(ctx: Context<CreateTrustAgreement>) -> Result<()> {
+        trust_agreement::_create_trust_agreement(ctx)
+    }
+
+    pub fn revoke_trust_agreement(ctx: Context<RevokeTrustAgreement>) -> Result<()> {
+        trust_agreement::_revoke_trust_agreement(ctx)
+    }
+
+    pub fn cross_wrapper_transfer(
+        ctx: Context<CrossWrapperTransfer>,
+        amount: u64,
+        memo: Option<String>,
+    ) -> Result<()> {
+        trust_agreement::_cross_wrapper_transfer(ctx, amount, memo)
+    }
+
+    // Baseline limits instructions
+
+    pub fn set_baseline_limits(
+        ctx: Context<SetBaselineLimits>,
+        baseline_limits: Option<BaselineLimits>,
+    ) -> Result<()> {
+        limits::_set_baseline_limits(ctx, baseline_limits)
+    }
+
+    pub fn initialize_limit_counter(ctx: Context<InitializeLimitCounter>) -> Result<()> {
+        limits::_initialize_limit_counter(ctx)
+    }
+
+    pub fn reset_limit_counter(ctx: Context<ResetLimitCounter>) -> Result<()> {
+        limits::_reset_limit_counter(ctx)
+    }
+
+    // Travel rule instructions
+
+    pub fn set_travel_rule_threshold(ctx: Context<SetTravelRuleThreshold>, threshold: Option<u64>) -> Result<()> {
+        travel_rule::_set_travel_rule_threshold(ctx, threshold)
+    }
+
+    pub fn create_travel_rule_record(
+        ctx: Context<CreateTravelRuleRecord>,
+        id: u64,
+        destination: Pubkey,
+        amount: u64,
+        data_hash: [u8; 32],
+    ) -> Result<()> {
+        travel_rule::_create_travel_rule_record(ctx, id, destination, amount, data_hash)
+    }
+
+    // Blocklist instructions
+
+    pub fn add_to_blocklist(ctx: Context<AddToBlocklist>, owner: Pubkey) -> Result<()> {
+        blocklist::_add_to_blocklist(ctx, owner)
+    }
+
+    pub fn remove_from_blocklist(ctx: Context<RemoveFromBlocklist>) -> Result<()> {
+        blocklist::_remove_from_blocklist(ctx)
     }
 
     // Bridge with external world
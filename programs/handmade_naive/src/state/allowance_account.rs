@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+// Amount a delegate can move out of a wrapped account on behalf of its owner
+#[account]
+pub struct Allowance {
+    pub wrapped_account: Pubkey,
+    pub delegate: Pubkey,
+    pub amount: u64,     // Remaining amount the delegate can spend, transfer fees included
+    pub expires_at: i64, // The allowance can't be used from this time
+}
+
+impl Allowance {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8;
+}
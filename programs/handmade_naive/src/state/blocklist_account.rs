@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+// Blocked owner of a wrapper, the existence of the PDA is the block
+#[account]
+pub struct BlocklistEntry {
+    pub wrapper_account: Pubkey,
+    pub owner: Pubkey,
+    pub blocked_at: i64,
+}
+
+impl BlocklistEntry {
+    pub const LEN: usize = 8 + 32 + 32 + 8;
+}
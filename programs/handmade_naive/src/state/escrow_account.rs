@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+use crate::error::EscrowError;
+
+// Funds locked out of a wrapped account until released to the receiver or refunded to the sender
+#[account]
+pub struct Escrow {
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub sender: Pubkey,
+    pub source_wrapped_account: Pubkey,
+    pub destination_wrapped_account: Pubkey,
+    pub arbiter: Option<Pubkey>, // Has to sign the release along with the receiver, without arbiter only the sender can release
+    pub amount: u64,
+    pub deadline: i64, // Released before, refunded from this time
+}
+
+impl Escrow {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 1 + 32 + 8 + 8;
+
+    /*
+        Released before the deadline by the sender alone, or by the receiver along with the arbiter
+    */
+    pub fn check_release(&self, releaser: Pubkey, receiver: Pubkey, arbiter: Option<Pubkey>, time: i64) -> Result<()> {
+        if time >= self.deadline {
+            return Err(EscrowError::DeadlinePassed.into());
+        }
+        if releaser == self.sender {
+            return Ok(());
+        }
+        if releaser != receiver {
+            return Err(EscrowError::ReleaseNotAuthorized.into());
+        }
+        match self.arbiter {
+            Some(escrow_arbiter) if arbiter == Some(escrow_arbiter) => Ok(()),
+            _ => Err(EscrowError::WrongArbiter.into()),
+        }
+    }
+
+    pub fn check_refund(&self, time: i64) -> Result<()> {
+        if time < self.deadline {
+            return Err(EscrowError::DeadlineNotPassed.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escrow(sender: Pubkey, arbiter: Option<Pubkey>) -> Escrow {
+        Escrow {
+            wrapper_account: Pubkey::default(),
+            mint: Pubkey::default(),
+            sender,
+            source_wrapped_account: Pubkey::default(),
+            destination_wrapped_account: Pubkey::default(),
+            arbiter,
+            amount: 10,
+            deadline: 100,
+        }
+    }
+
+    #[test]
+    fn test_release_by_sender() {
+        let (sender, receiver, arbiter) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(escrow(sender, None).check_release(sender, receiver, None, 99).is_ok());
+        assert!(escrow(sender, Some(arbiter)).check_release(sender, receiver, None, 99).is_ok());
+        assert!(escrow(sender, None).check_release(sender, receiver, None, 100).is_err());
+    }
+
+    #[test]
+    fn test_release_by_arbiter() {
+        let (sender, receiver, arbiter) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let escrow_with_arbiter = escrow(sender, Some(arbiter));
+        assert!(escrow_with_arbiter.check_release(receiver, receiver, Some(arbiter), 99).is_ok());
+        assert!(escrow_with_arbiter.check_release(receiver, receiver, Some(Pubkey::new_unique()), 99).is_err());
+        assert!(escrow_with_arbiter.check_release(receiver, receiver, None, 99).is_err());
+        assert!(escrow_with_arbiter.check_release(arbiter, receiver, Some(arbiter), 99).is_err());
+        assert!(escrow_with_arbiter.check_release(receiver, receiver, Some(arbiter), 100).is_err());
+        // Without arbiter the receiver can't release alone
+        assert!(escrow(sender, None).check_release(receiver, receiver, None, 99).is_err());
+    }
+
+    #[test]
+    fn test_refund() {
+        let sender = Pubkey::new_unique();
+        assert!(escrow(sender, None).check_refund(99).is_err());
+        assert!(escrow(sender, None).check_refund(100).is_ok());
+    }
+}
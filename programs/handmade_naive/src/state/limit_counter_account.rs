@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::{BaselineLimits, CircularTimeWindow, Duration};
+
+// Outgoing transfers of a wrapped account, counted against the baseline limits of its wrapper
+#[account]
+pub struct LimitCounter {
+    pub wrapped_account: Pubkey,
+    pub amounts: CircularTimeWindow,
+    pub transactions: CircularTimeWindow,
+}
+
+impl LimitCounter {
+    pub fn get_len(duration: &Duration) -> usize {
+        8 + 32 + 2 * CircularTimeWindow::new(duration.clone(), 0).get_init_len()
+    }
+
+    pub fn new(wrapped_account: Pubkey, duration: &Duration, time: i64) -> Self {
+        LimitCounter {
+            wrapped_account,
+            amounts: CircularTimeWindow::new(duration.clone(), time),
+            transactions: CircularTimeWindow::new(duration.clone(), time),
+        }
+    }
+
+    // The windows are sized for a duration, a counter created for other limits has to be reset
+    pub fn is_outdated(&self, baseline_limits: &BaselineLimits) -> bool {
+        self.amounts.get_duration() != baseline_limits.duration
+    }
+
+    pub fn get_remaining_amount(&self, baseline_limits: &BaselineLimits, time: i64) -> u64 {
+        baseline_limits.max_amount.saturating_sub(self.amounts.get_count_at(time))
+    }
+
+    pub fn get_remaining_transactions(&self, baseline_limits: &BaselineLimits, time: i64) -> u64 {
+        baseline_limits.max_transactions.saturating_sub(self.transactions.get_count_at(time))
+    }
+
+    pub fn add(&mut self, amount: u64, transactions: u64, time: i64) {
+        self.amounts.add(time, amount);
+        self.transactions.add(time, transactions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_counter_remaining() {
+        let day = 86400;
+        let limits = BaselineLimits { duration: Duration::Days(1), max_amount: 100, max_transactions: 2 };
+        let mut counter = LimitCounter::new(Pubkey::new_unique(), &limits.duration, 0);
+        assert!(!counter.is_outdated(&limits));
+
+        counter.add(60, 1, 10);
+        assert_eq!(counter.get_remaining_amount(&limits, 20), 40);
+        assert_eq!(counter.get_remaining_transactions(&limits, 20), 1);
+
+        // The window has moved, the previous transfers are not counted anymore
+        assert_eq!(counter.get_remaining_amount(&limits, 2 * day), 100);
+        assert_eq!(counter.get_remaining_transactions(&limits, 2 * day), 2);
+
+        let limits = BaselineLimits { duration: Duration::Days(7), ..limits };
+        assert!(counter.is_outdated(&limits));
+    }
+}
@@ -11,4 +11,37 @@ pub mod wrapper_account;
 pub use wrapper_account::*;
 
 pub mod recovery_account;
-pub use recovery_account::*;
\ No newline at end of file
+pub use recovery_account::*;
+
+pub mod white_list_account;
+pub use white_list_account::*;
+
+pub mod price_account;
+pub use price_account::*;
+
+pub mod allowance_account;
+pub use allowance_account::*;
+
+pub mod scheduled_transfer_account;
+pub use scheduled_transfer_account::*;
+
+pub mod escrow_account;
+pub use escrow_account::*;
+
+pub mod treasury_account;
+pub use treasury_account::*;
+
+pub mod trust_agreement_account;
+pub use trust_agreement_account::*;
+
+pub mod limit_counter_account;
+pub use limit_counter_account::*;
+
+pub mod travel_rule_account;
+pub use travel_rule_account::*;
+
+pub mod blocklist_account;
+pub use blocklist_account::*;
+
+pub mod nullifier_account;
+pub use nullifier_account::*;
@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::NullifierError, IdAccount};
+
+// One identity per subject for an issuer, the nullifier is a hash of the subject's document ID
+#[account]
+pub struct NullifierAccount {
+    pub issuer: Pubkey,
+    pub nullifier: [u8; 32],
+    pub idendity: Pubkey, // Identity currently bound to the nullifier, default until the first registration
+    pub created_at: i64,
+}
+
+impl NullifierAccount {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8;
+
+    /*
+        Whether the nullifier has to be bound to the identity: registering again the identity already bound is a no-op,
+        the nullifier only moves from another identity once it has been recovered
+    */
+    pub fn needs_binding(&self, idendity: Pubkey, previous_idendity: Option<(Pubkey, &IdAccount)>) -> Result<bool> {
+        if self.idendity == Pubkey::default() {
+            return Ok(true);
+        }
+        if self.idendity == idendity {
+            return Ok(false);
+        }
+        let (previous_key, previous_account) = previous_idendity.ok_or(NullifierError::MissingPreviousIdendity)?;
+        if previous_key != self.idendity {
+            return Err(NullifierError::MissingPreviousIdendity.into());
+        }
+        if previous_account.recovered_address.is_none() {
+            return Err(NullifierError::NullifierAlreadyUsed.into());
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nullifier_account(idendity: Pubkey) -> NullifierAccount {
+        NullifierAccount {
+            issuer: Pubkey::new_unique(),
+            nullifier: [1; 32],
+            idendity,
+            created_at: 0,
+        }
+    }
+
+    fn id_account(recovered_address: Option<Pubkey>) -> IdAccount {
+        IdAccount {
+            owner: Pubkey::new_unique(),
+            issuers: vec![],
+            recovered_address,
+        }
+    }
+
+    #[test]
+    fn test_first_registration() {
+        assert!(nullifier_account(Pubkey::default()).needs_binding(Pubkey::new_unique(), None).unwrap());
+    }
+
+    #[test]
+    fn test_same_idendity_is_noop() {
+        let idendity = Pubkey::new_unique();
+        assert!(!nullifier_account(idendity).needs_binding(idendity, None).unwrap());
+    }
+
+    #[test]
+    fn test_move_from_previous_idendity() {
+        let (previous, idendity) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let bound = nullifier_account(previous);
+        let recovered = id_account(Some(Pubkey::new_unique()));
+        let active = id_account(None);
+        assert!(bound.needs_binding(idendity, Some((previous, &recovered))).unwrap());
+        assert!(bound.needs_binding(idendity, Some((previous, &active))).is_err());
+        assert!(bound.needs_binding(idendity, Some((Pubkey::new_unique(), &recovered))).is_err());
+        assert!(bound.needs_binding(idendity, None).is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+
+use crate::error::TwoAuthError;
+
+// Price of a mint in the reference currency of the wrapper (euro cents for example)
+// Used to express the two auth max values in the reference currency instead of the mint's base units
+#[account]
+pub struct PriceAccount {
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub price_authority: Pubkey, // Designated by the approver of the wrapper, the only one able to update the price
+    pub price: u64,              // Value of one whole token (10^decimals base units) in the reference currency
+    pub decimals: u8,            // Decimals of the mint
+    pub last_update: i64,
+}
+
+impl PriceAccount {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1 + 8;
+
+    /*
+        Value in the reference currency of an amount of the mint's base units
+        Rounded up: otherwise small transfers would be worth 0 and could be split to get around the limits
+        Saturates instead of failing: a huge value will anyway need the two auth
+        Fails if 10^decimals does not fit in a u128
+    */
+    pub fn get_value(&self, amount: u64) -> Result<u64> {
+        let unit = 10u128.checked_pow(self.decimals as u32).ok_or(TwoAuthError::InvalidPriceDecimals)?;
+        let value = ((amount as u128) * (self.price as u128)).div_ceil(unit);
+        Ok(u64::try_from(value).unwrap_or(u64::MAX))
+    }
+
+    pub fn is_stale(&self, time: i64, max_age: i64) -> bool {
+        time.saturating_sub(self.last_update) > max_age
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price_account(price: u64, decimals: u8) -> PriceAccount {
+        PriceAccount {
+            wrapper_account: Pubkey::default(),
+            mint: Pubkey::default(),
+            price_authority: Pubkey::default(),
+            price,
+            decimals,
+            last_update: 0,
+        }
+    }
+
+    #[test]
+    fn test_get_value() {
+        assert_eq!(price_account(150, 6).get_value(2_000_000).unwrap(), 300);
+        assert_eq!(price_account(u64::MAX, 0).get_value(u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(price_account(1, 38).get_value(u64::MAX).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_get_value_below_one_unit() {
+        // 0.5 token at 1 cent is worth 1 cent, not 0
+        assert_eq!(price_account(1, 6).get_value(500_000).unwrap(), 1);
+        assert_eq!(price_account(150, 6).get_value(1).unwrap(), 1);
+        assert_eq!(price_account(150, 6).get_value(0).unwrap(), 0);
+        assert_eq!(price_account(150, 6).get_value(2_000_001).unwrap(), 301);
+    }
+
+    #[test]
+    fn test_get_value_too_many_decimals() {
+        assert!(price_account(1, 39).get_value(1).is_err());
+        assert!(price_account(1, u8::MAX).get_value(1).is_err());
+    }
+}
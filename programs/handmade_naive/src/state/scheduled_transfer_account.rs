@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ScheduledTransferError;
+
+// Transfer pre-authorized by the owner, executed by anyone once due (rent, salaries...)
+#[account]
+pub struct ScheduledTransfer {
+    pub source_wrapped_account: Pubkey,
+    pub destination_wrapped_account: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub interval: i64,        // Seconds between two executions
+    pub next_execution: i64,  // The transfer can be executed from this time
+    pub remaining_count: u32, // Executions left, the account is closed after the last one
+    pub memo: Option<String>,
+}
+
+impl ScheduledTransfer {
+    pub fn get_init_len(memo: &Option<String>) -> usize {
+        8 + 32 + 32 + 32 + 8 + 8 + 8 + 4 + 1 + memo.as_ref().map_or(0, |memo| 4 + memo.len())
+    }
+
+    /*
+        Consumes one execution and returns true if it was the last one
+        The executions missed while nobody executed the transfer are not caught up: the next execution is the first time of the schedule
+        after `time`, so a late execution never allows a second one in the same slot. The remaining count is unchanged by the missed ones
+    */
+    pub fn consume_execution(&mut self, time: i64) -> Result<bool> {
+        if time < self.next_execution {
+            return Err(ScheduledTransferError::NotDue.into());
+        }
+        let elapsed_intervals = (time - self.next_execution) / self.interval + 1;
+        self.next_execution = self.next_execution.saturating_add(elapsed_intervals.saturating_mul(self.interval));
+        self.remaining_count -= 1;
+        Ok(self.remaining_count == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduled_transfer(next_execution: i64, interval: i64, remaining_count: u32) -> ScheduledTransfer {
+        ScheduledTransfer {
+            source_wrapped_account: Pubkey::default(),
+            destination_wrapped_account: Pubkey::default(),
+            owner: Pubkey::default(),
+            amount: 10,
+            interval,
+            next_execution,
+            remaining_count,
+            memo: None,
+        }
+    }
+
+    #[test]
+    fn test_not_due() {
+        let mut scheduled_transfer = scheduled_transfer(100, 10, 2);
+        assert!(scheduled_transfer.consume_execution(99).is_err());
+        assert_eq!(scheduled_transfer.next_execution, 100);
+        assert_eq!(scheduled_transfer.remaining_count, 2);
+    }
+
+    #[test]
+    fn test_due() {
+        let mut scheduled_transfer = scheduled_transfer(100, 10, 3);
+        assert!(!scheduled_transfer.consume_execution(100).unwrap());
+        assert_eq!(scheduled_transfer.next_execution, 110);
+        assert_eq!(scheduled_transfer.remaining_count, 2);
+        assert!(scheduled_transfer.consume_execution(105).is_err());
+    }
+
+    #[test]
+    fn test_missed_executions_are_not_caught_up() {
+        let mut scheduled_transfer = scheduled_transfer(100, 10, 5);
+        // Three slots missed, one execution and the next one is in the following slot
+        assert!(!scheduled_transfer.consume_execution(135).unwrap());
+        assert_eq!(scheduled_transfer.next_execution, 140);
+        assert_eq!(scheduled_transfer.remaining_count, 4);
+        assert!(scheduled_transfer.consume_execution(135).is_err());
+        assert!(scheduled_transfer.consume_execution(139).is_err());
+        assert!(!scheduled_transfer.consume_execution(140).unwrap());
+    }
+
+    #[test]
+    fn test_last_execution() {
+        let mut scheduled_transfer = scheduled_transfer(100, 10, 1);
+        assert!(scheduled_transfer.consume_execution(100).unwrap());
+        assert_eq!(scheduled_transfer.remaining_count, 0);
+    }
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+// Commitment to the originator and beneficiary data of one transfer, the personal data stays off chain
+// Signed by an issuer of the sender and closed by the transfer it is bound to
+#[account]
+pub struct TravelRuleRecord {
+    pub wrapper_account: Pubkey,
+    pub source_wrapped_account: Pubkey,
+    pub destination: Pubkey, // Wrapped account, or token account for transfer_out
+    pub amount: u64,
+    pub data_hash: [u8; 32],
+    pub issuer: Pubkey,
+    pub created_at: i64,
+}
+
+impl TravelRuleRecord {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 32 + 32 + 8;
+
+    pub fn is_bound_to(&self, source_wrapped_account: &Pubkey, destination: &Pubkey, amount: u64) -> bool {
+        self.source_wrapped_account == *source_wrapped_account && self.destination == *destination && self.amount == amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn travel_rule_record_binding() {
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let record = TravelRuleRecord {
+            wrapper_account: Pubkey::new_unique(),
+            source_wrapped_account: source,
+            destination,
+            amount: 1_000,
+            data_hash: [1; 32],
+            issuer: Pubkey::new_unique(),
+            created_at: 0,
+        };
+        assert!(record.is_bound_to(&source, &destination, 1_000));
+        assert!(!record.is_bound_to(&source, &destination, 999));
+        assert!(!record.is_bound_to(&destination, &source, 1_000));
+    }
+}
@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+// Splits the tokens held by the wrapper for a mint between the users and the wrapper itself
+// Backing invariant: wrapper_token_account.amount >= user_supply + collected_fees
+#[account]
+pub struct Treasury {
+    pub wrapper_account: Pubkey,
+    pub mint: Pubkey,
+    pub wrap_fee_basis_points: u16,   // Entry fee, kept on the wrapped amount
+    pub unwrap_fee_basis_points: u16, // Exit fee, kept on the unwrapped amount
+    pub user_supply: u64,             // Sum of the wrapped amounts owed to the users
+    pub collected_fees: u64,          // Wrap and unwrap fees owned by the wrapper
+}
+
+impl Treasury {
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 2 + 8 + 8;
+
+    pub fn get_wrap_fee(&self, amount: u64) -> u64 {
+        get_fee(amount, self.wrap_fee_basis_points)
+    }
+
+    pub fn get_unwrap_fee(&self, amount: u64) -> u64 {
+        get_fee(amount, self.unwrap_fee_basis_points)
+    }
+
+    pub fn is_backed(&self, wrapper_token_amount: u64) -> bool {
+        (wrapper_token_amount as u128) >= (self.user_supply as u128) + (self.collected_fees as u128)
+    }
+}
+
+fn get_fee(amount: u64, basis_points: u16) -> u64 {
+    // basis_points <= 10_000, never more than amount
+    ((amount as u128) * (basis_points as u128) / 10_000) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn treasury_fees_and_backing() {
+        let mut treasury = Treasury {
+            wrapper_account: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            wrap_fee_basis_points: 100,
+            unwrap_fee_basis_points: 0,
+            user_supply: 0,
+            collected_fees: 0,
+        };
+        let fee = treasury.get_wrap_fee(1_000);
+        assert_eq!(fee, 10);
+        assert_eq!(treasury.get_unwrap_fee(1_000), 0);
+
+        treasury.user_supply = 1_000 - fee;
+        treasury.collected_fees = fee;
+        assert!(treasury.is_backed(1_000));
+        assert!(!treasury.is_backed(999));
+    }
+}
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+// Signed by the approvers of both wrappers: their holders can pay each other and each wrapper accepts the other's issuers
+// wrapper_a < wrapper_b, so there is a single agreement for a pair of wrappers
+#[account]
+pub struct TrustAgreement {
+    pub wrapper_a: Pubkey,
+    pub wrapper_b: Pubkey,
+}
+
+impl TrustAgreement {
+    pub const LEN: usize = 8 + 32 + 32;
+
+    pub fn links(&self, wrapper_account: &Pubkey) -> bool {
+        self.wrapper_a == *wrapper_account || self.wrapper_b == *wrapper_account
+    }
+
+    pub fn is_between(&self, wrapper_account: &Pubkey, other_wrapper_account: &Pubkey) -> bool {
+        wrapper_account != other_wrapper_account && self.links(wrapper_account) && self.links(other_wrapper_account)
+    }
+}
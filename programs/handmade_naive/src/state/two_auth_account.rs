@@ -1,35 +1,103 @@
 use anchor_lang::prelude::*;
 
+use crate::error::TwoAuthError;
+
+/*
+    The functions are evaluated in order and the first one giving an answer wins: a white list placed first exempts its receivers from the next functions
+    The rule is then evaluated on its own and ORed with the answer of the functions: a white list in the functions doesn't exempt from the rule,
+    the rule has to exclude the white list itself (All with a Not of the white list)
+*/
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct TwoAuthParameters {
     pub functions: Vec<TwoAuthFunction>, // TwoAuthFunction::get_init_len()
+    pub rule: Option<TwoAuthRule>, // 1 + TwoAuthRule::get_init_len() - ORed with the first-match answer of the functions
     pub two_auth_entity: Pubkey, // 32 - Also called Insurance // We could had several keys for several levels of insurance (In case our keys to be stolen)
     pub allowed_issuers: Vec<Pubkey>, // 4 + 32 * len
+    pub price_max_age: Option<i64>, // 1 + 8 - When set, the max values are in the reference currency of the PriceAccount, which must be updated less than price_max_age seconds ago
+    pub downgrade_delay: i64, // 8 - Delay before an update scheduled by the owner alone takes effect
+}
+
+impl TwoAuthParameters {
+    pub fn get_len(&self) -> usize {
+        let functions_space = self.functions.iter().map(|f| f.get_init_len()).sum::<usize>();
+        let rule_space = 1 + self.rule.as_ref().map_or(0, |r| r.get_init_len());
+        4 + functions_space + rule_space + 32 + 4 + 32 * self.allowed_issuers.len() + 1 + 8 + 8
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct TwoAuthArgs {
     pub functions: Vec<TwoAuthFunction>,
+    pub rule: Option<TwoAuthRule>,
     pub allowed_issuers: Vec<Pubkey>,
+    pub price_max_age: Option<i64>,
+    pub downgrade_delay: i64,
+}
+
+impl TwoAuthArgs {
+    // Length of the TwoAuthParameters created from these args
+    pub fn get_parameters_len(&self) -> usize {
+        let functions_space = self.functions.iter().map(|f| f.get_init_len()).sum::<usize>();
+        let rule_space = 1 + self.rule.as_ref().map_or(0, |r| r.get_init_len());
+        4 + functions_space + rule_space + 32 + 4 + 32 * self.allowed_issuers.len() + 1 + 8 + 8
+    }
 }
 
 #[account]
 pub struct TwoAuth {
     pub two_auth: Option<TwoAuthParameters>,
+    pub pending_update: Option<PendingTwoAuthUpdate>, // Update scheduled by the owner without the two auth entity
+}
+
+// Removal or loosening of the policy by the owner alone, the two auth entity can block it until effective_at
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PendingTwoAuthUpdate {
+    pub two_auth: Option<TwoAuthParameters>,
+    pub effective_at: i64,
 }
 
 impl TwoAuth {
     pub fn get_init_len(two_auth_args: &Option<TwoAuthArgs>) -> usize {
-        match two_auth_args {
-            Some(TwoAuthArgs {
-                functions,
-                allowed_issuers,
-            }) => {
-                let functions_space = functions.iter().map(|f| f.get_init_len()).sum::<usize>();
-                return 8 + 1 + 4 + functions_space + 32 + 4 + 32 * allowed_issuers.len();
-            }
-            None => 8 + 1,
+        8 + 1 + two_auth_args.as_ref().map_or(0, |args| args.get_parameters_len()) + 1
+    }
+
+    pub fn get_schedule_update_len(&self, two_auth_args: &Option<TwoAuthArgs>) -> usize {
+        let current_space = self.two_auth.as_ref().map_or(0, |p| p.get_len());
+        let pending_space = 1 + two_auth_args.as_ref().map_or(0, |args| args.get_parameters_len()) + 8;
+        8 + 1 + current_space + 1 + pending_space
+    }
+
+    pub fn get_apply_update_len(&self) -> usize {
+        let pending_space = match &self.pending_update {
+            Some(PendingTwoAuthUpdate { two_auth: Some(parameters), .. }) => parameters.get_len(),
+            _ => 0,
+        };
+        8 + 1 + pending_space + 1
+    }
+
+    /*
+        The owner can cancel the pending update at any time, the two auth entity only until it takes effect:
+        past effective_at, the entity can't block the owner's downgrade anymore
+    */
+    pub fn check_cancel_update(&self, authority: Pubkey, owner: Pubkey, time: i64) -> Result<()> {
+        let is_two_auth_entity = self.two_auth.as_ref().is_some_and(|p| p.two_auth_entity == authority);
+        if authority != owner && !is_two_auth_entity {
+            return Err(TwoAuthError::NotAuthorized.into());
         }
+        let pending_update = self.pending_update.as_ref().ok_or(TwoAuthError::NoPendingUpdate)?;
+        if authority != owner && time >= pending_update.effective_at {
+            return Err(TwoAuthError::DowngradeDelayPassed.into());
+        }
+        Ok(())
+    }
+
+    // Over estimates when there was no rule, the old and the new rules are kept and joined by an Any node
+    pub fn get_tighten_len(&self, rule: &TwoAuthRule) -> usize {
+        let current_space = self.two_auth.as_ref().map_or(0, |p| p.get_len());
+        let pending_space = self.pending_update.as_ref().map_or(0, |pending| {
+            1 + pending.two_auth.as_ref().map_or(0, |p| p.get_len()) + 8
+        });
+        8 + 1 + current_space + rule.get_init_len() + TwoAuthRuleNode::get_any_len(2) + 1 + pending_space
     }
 }
 
@@ -55,10 +123,15 @@ pub enum TwoAuthFunction {
         max: u64,
         window: CircularTimeWindow,
     },
-    // DeactivateForGeneralWhiteList, // This white list is derived from the receiver address: the insurance has to add their addresss to the white list (to white list the receiver token account)
     DeactivateForUserSpecificWhiteList {
         white_list: Vec<Pubkey>,
     },
+    TransactionCounterWithTimeWindow {
+        // Counts the number of transfers instead of the amount, usually over a day
+        max: u64,
+        window: CircularTimeWindow,
+    },
+    DeactivateForGeneralWhiteList, // This white list is the GeneralWhiteList PDA of the two auth entity: the insurance has to add the receiver address to it
 }
 
 impl TwoAuthFunction {
@@ -74,6 +147,122 @@ impl TwoAuthFunction {
             TwoAuthFunction::DeactivateForUserSpecificWhiteList { white_list } => {
                 1 + 4 + 32 * white_list.len()
             }
+            TwoAuthFunction::TransactionCounterWithTimeWindow { max: _, window } => {
+                1 + 8 + window.get_init_len()
+            }
+            TwoAuthFunction::DeactivateForGeneralWhiteList => 1,
+        }
+    }
+
+    /*
+        A zero duration would give an empty window: adding to it would divide by zero
+    */
+    pub fn is_valid(&self) -> bool {
+        match self {
+            TwoAuthFunction::CounterResetOnTime { duration, .. } => duration.get() > 0,
+            TwoAuthFunction::CounterWithTimeWindow { window, .. }
+            | TwoAuthFunction::TransactionCounterWithTimeWindow { window, .. } => window.get_duration().get() > 0,
+            _ => true,
+        }
+    }
+
+    /*
+        Returns (counter, max) for the counter functions as seen at the given time, without modifying them
+        The counter of TransactionCounterWithTimeWindow is a number of transfers
+    */
+    pub fn get_counter(&self, time: i64) -> Option<(u64, u64)> {
+        match self {
+            TwoAuthFunction::CounterResetOnMax { max, counter } => Some((*counter, *max)),
+            TwoAuthFunction::CounterResetOnTime { max, duration, counter, last_reset_time } => {
+                if time.saturating_sub(*last_reset_time) > duration.get_seconds() {
+                    Some((0, *max))
+                } else {
+                    Some((*counter, *max))
+                }
+            }
+            TwoAuthFunction::CounterWithTimeWindow { max, window }
+            | TwoAuthFunction::TransactionCounterWithTimeWindow { max, window } => {
+                Some((window.get_count_at(time), *max))
+            }
+            _ => None,
+        }
+    }
+}
+
+/*
+    Boolean expression over TwoAuthFunction, the two auth is needed when the rule is true
+    A leaf is true when its function matches: Always, amount/counter over max, receiver in the white list...
+    The nodes are stored flat: a node only refers to nodes placed before it and the last node is the root
+    Example: [OnMax { max }, DeactivateForUserSpecificWhiteList { white_list }, Not(1), All([0, 2])]
+*/
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TwoAuthRule {
+    pub nodes: Vec<TwoAuthRuleNode>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum TwoAuthRuleNode {
+    Function(TwoAuthFunction),
+    All(Vec<u8>), // Indexes of the children nodes
+    Any(Vec<u8>),
+    Not(u8),
+}
+
+impl TwoAuthRule {
+    pub fn get_init_len(&self) -> usize {
+        4 + self.nodes.iter().map(|n| n.get_init_len()).sum::<usize>()
+    }
+
+    /*
+        Checks that the rule has a root, that every node only refers to the nodes before it
+        and that every leaf function is valid
+    */
+    pub fn is_valid(&self) -> bool {
+        if self.nodes.is_empty() || self.nodes.len() > u8::MAX as usize {
+            return false;
+        }
+        self.nodes.iter().enumerate().all(|(index, node)| match node {
+            TwoAuthRuleNode::Function(function) => function.is_valid(),
+            TwoAuthRuleNode::All(children) | TwoAuthRuleNode::Any(children) => {
+                children.iter().all(|child| (*child as usize) < index)
+            }
+            TwoAuthRuleNode::Not(child) => (*child as usize) < index,
+        })
+    }
+
+    /*
+        Returns the rule that is true when this rule or the other one is true
+        Only makes the two auth needed more often: used to tighten a policy without the two auth entity
+        Both rules must be valid, returns None if the joined rule would have too many nodes
+    */
+    pub fn or(&self, other: &TwoAuthRule) -> Option<TwoAuthRule> {
+        if self.nodes.len() + other.nodes.len() + 1 > u8::MAX as usize {
+            return None;
+        }
+        let offset = self.nodes.len() as u8;
+        let other_nodes = other.nodes.iter().map(|node| match node {
+            TwoAuthRuleNode::Function(function) => TwoAuthRuleNode::Function(function.clone()),
+            TwoAuthRuleNode::All(children) => TwoAuthRuleNode::All(children.iter().map(|c| c + offset).collect()),
+            TwoAuthRuleNode::Any(children) => TwoAuthRuleNode::Any(children.iter().map(|c| c + offset).collect()),
+            TwoAuthRuleNode::Not(child) => TwoAuthRuleNode::Not(child + offset),
+        });
+        let mut nodes: Vec<TwoAuthRuleNode> = self.nodes.iter().cloned().chain(other_nodes).collect();
+        let roots = vec![offset - 1, nodes.len() as u8 - 1];
+        nodes.push(TwoAuthRuleNode::Any(roots));
+        Some(TwoAuthRule { nodes })
+    }
+}
+
+impl TwoAuthRuleNode {
+    pub fn get_any_len(children: usize) -> usize {
+        1 + 4 + children
+    }
+
+    pub fn get_init_len(&self) -> usize {
+        match self {
+            TwoAuthRuleNode::Function(function) => 1 + function.get_init_len(),
+            TwoAuthRuleNode::All(children) | TwoAuthRuleNode::Any(children) => 1 + 4 + children.len(),
+            TwoAuthRuleNode::Not(_) => 1 + 1,
         }
     }
 }
@@ -107,7 +296,7 @@ impl CircularTimeWindow {
     pub fn add(&mut self, time: i64, value: u64) {
         let diff = self.get_time_difference_duration(time);
         if diff == 0 {
-            self.window[self.start_index as usize] += value;
+            self.window[self.start_index as usize] = self.window[self.start_index as usize].saturating_add(value);
         } else {
             let new_index = (self.start_index as usize + diff as usize) % self.window.len();
             self.circular_reset_values_between_indexes(self.start_index as usize, new_index);
@@ -122,7 +311,16 @@ impl CircularTimeWindow {
     }
 
     pub fn get_count(&self) -> u64 {
-        return self.window.iter().sum();
+        return self.window.iter().fold(0, |count, value| count.saturating_add(*value));
+    }
+
+    /*
+        Count of the window if it was moved to the given time
+    */
+    pub fn get_count_at(&self, time: i64) -> u64 {
+        let mut window = self.clone();
+        window.add(time, 0);
+        window.get_count()
     }
 
     /*
@@ -169,7 +367,7 @@ impl CircularTimeWindow {
     }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
 pub enum Duration {
     // Space = 1 + 1 = 2
     Seconds(u8),
@@ -191,10 +389,119 @@ impl Duration {
             | Duration::Weeks(t) => *t,
         }
     }
+
+    pub fn get_seconds(&self) -> i64 {
+        match self {
+            Duration::Seconds(t) => *t as i64,
+            Duration::Minutes(t) => *t as i64 * 60,
+            Duration::Hours(t) => *t as i64 * 3600,
+            Duration::Days(t) => *t as i64 * 86400,
+            Duration::Weeks(t) => *t as i64 * 604800,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use anchor_lang::AnchorSerialize;
+
+    #[test]
+    fn two_auth_rule_init_len() {
+        let rule = super::TwoAuthRule {
+            nodes: vec![
+                super::TwoAuthRuleNode::Function(super::TwoAuthFunction::OnMax { max: 10 }),
+                super::TwoAuthRuleNode::Function(super::TwoAuthFunction::DeactivateForUserSpecificWhiteList {
+                    white_list: vec![anchor_lang::prelude::Pubkey::new_unique()],
+                }),
+                super::TwoAuthRuleNode::Not(1),
+                super::TwoAuthRuleNode::All(vec![0, 2]),
+            ],
+        };
+        assert_eq!(rule.get_init_len(), rule.try_to_vec().unwrap().len());
+        assert!(rule.is_valid());
+    }
+
+    #[test]
+    fn two_auth_rule_is_valid() {
+        let rule = super::TwoAuthRule { nodes: vec![] };
+        assert!(!rule.is_valid());
+        let rule = super::TwoAuthRule {
+            nodes: vec![
+                super::TwoAuthRuleNode::Not(1),
+                super::TwoAuthRuleNode::Function(super::TwoAuthFunction::Always),
+            ],
+        };
+        assert!(!rule.is_valid());
+    }
+
+    #[test]
+    fn two_auth_rule_or() {
+        let rule = super::TwoAuthRule {
+            nodes: vec![
+                super::TwoAuthRuleNode::Function(super::TwoAuthFunction::OnMax { max: 10 }),
+                super::TwoAuthRuleNode::Not(0),
+            ],
+        };
+        let other = super::TwoAuthRule {
+            nodes: vec![super::TwoAuthRuleNode::Function(super::TwoAuthFunction::Always)],
+        };
+        let joined = rule.or(&other).unwrap();
+        assert!(joined.is_valid());
+        assert_eq!(joined.nodes.len(), 4);
+        assert!(matches!(&joined.nodes[3], super::TwoAuthRuleNode::Any(children) if *children == vec![1, 2]));
+    }
+
+    #[test]
+    fn zero_duration_is_invalid() {
+        let window = |duration| super::TwoAuthFunction::CounterWithTimeWindow {
+            max: 10,
+            window: super::CircularTimeWindow::new(duration, 0),
+        };
+        assert!(!window(super::Duration::Days(0)).is_valid());
+        assert!(window(super::Duration::Days(30)).is_valid());
+        assert!(!super::TwoAuthFunction::TransactionCounterWithTimeWindow {
+            max: 10,
+            window: super::CircularTimeWindow::new(super::Duration::Hours(0), 0),
+        }.is_valid());
+        assert!(!super::TwoAuthFunction::CounterResetOnTime {
+            max: 10,
+            duration: super::Duration::Seconds(0),
+            counter: 0,
+            last_reset_time: 0,
+        }.is_valid());
+
+        let rule = |duration| super::TwoAuthRule {
+            nodes: vec![super::TwoAuthRuleNode::Function(window(duration))],
+        };
+        assert!(!rule(super::Duration::Weeks(0)).is_valid());
+        assert!(rule(super::Duration::Weeks(1)).is_valid());
+    }
+
+    #[test]
+    fn cancel_two_auth_update() {
+        use anchor_lang::prelude::Pubkey;
+        let (owner, two_auth_entity) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut two_auth = super::TwoAuth {
+            two_auth: Some(super::TwoAuthParameters {
+                functions: vec![super::TwoAuthFunction::Always],
+                rule: None,
+                two_auth_entity,
+                allowed_issuers: vec![],
+                price_max_age: None,
+                downgrade_delay: 100,
+            }),
+            pending_update: None,
+        };
+        assert!(two_auth.check_cancel_update(owner, owner, 0).is_err());
+        two_auth.pending_update = Some(super::PendingTwoAuthUpdate { two_auth: None, effective_at: 100 });
+
+        assert!(two_auth.check_cancel_update(two_auth_entity, owner, 99).is_ok());
+        // Past effective_at, only the owner can still cancel
+        assert!(two_auth.check_cancel_update(two_auth_entity, owner, 100).is_err());
+        assert!(two_auth.check_cancel_update(owner, owner, 100).is_ok());
+        assert!(two_auth.check_cancel_update(Pubkey::new_unique(), owner, 0).is_err());
+    }
+
     #[test]
     fn circular_time_window() {
         let window = super::CircularTimeWindow::new(super::Duration::Days(30), 0);
@@ -240,4 +547,16 @@ mod tests {
         assert_eq!(window.get_count(), 2);
         assert_eq!(window.window[1], 0);
     }
+
+    #[test]
+    fn circular_time_window_get_count_at() {
+        let day = 86400;
+        let mut window = super::CircularTimeWindow::new(super::Duration::Days(3), 0);
+        window.add(0, 2);
+        window.add(day, 1);
+        assert_eq!(window.get_count_at(day), 3);
+        assert_eq!(window.get_count_at(3 * day), 1);
+        assert_eq!(window.get_count_at(10 * day), 0);
+        assert_eq!(window.get_count(), 3);
+    }
 }
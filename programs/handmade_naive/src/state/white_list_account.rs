@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+// White list shared by all the users that chose the same two auth entity (usually an insurance)
+// The two auth entity adds the addresses it trusts (merchants...) so that the users don't have to update their own white list
+#[account]
+pub struct GeneralWhiteList {
+    pub two_auth_entity: Pubkey,
+    pub white_list: Vec<Pubkey>,
+}
+
+impl GeneralWhiteList {
+    pub fn get_init_len(white_list: &[Pubkey]) -> usize {
+        8 + 32 + 4 + 32 * white_list.len()
+    }
+
+    pub fn get_add_address_len(&self) -> usize {
+        8 + 32 + 4 + 32 * self.white_list.len() + 32
+    }
+
+    pub fn get_remove_address_len(&self) -> usize {
+        8 + 32 + 4 + 32 * self.white_list.len() - 32
+    }
+}
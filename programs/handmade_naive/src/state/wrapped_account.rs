@@ -7,9 +7,10 @@ pub struct WrappedTokenAccount {
     pub owner: Pubkey,
     pub amount: u64,
     pub last_tx: i64, // Last transaction timestamp
+    pub require_memo: bool, // Incoming transfers need a memo
 }
 
 impl WrappedTokenAccount {
 
-    pub const LEN : usize = 8 + 32 + 32 + 32 + 8 + 8 ;
+    pub const LEN : usize = 8 + 32 + 32 + 32 + 8 + 8 + 1;
 }
@@ -1,21 +1,136 @@
 use anchor_lang::prelude::*;
 
+use crate::Duration;
+
 #[account]
 pub struct WrapperAccount {
     pub approver: Pubkey,
     pub list_issuer: Vec<Pubkey>,
+    pub fee_schedule: Option<FeeSchedule>, // Fee paid by the sender on each transfer
+    pub allow_unverified_recipients: bool,  // transfer_out can pay token accounts whose owner has no identity
+    pub baseline_limits: Option<BaselineLimits>, // Apply to every wrapped account, whatever its two auth
+    pub travel_rule_threshold: Option<u64>,      // Transfers of at least this amount need a travel rule record, 1 + 8 always allocated
 }
 
 impl WrapperAccount {
     pub fn get_init_len(list_issuer: Vec<Pubkey>) -> usize {
-        return 8 + 32 + 4 + 32 * list_issuer.len();
+        return 8 + 32 + 4 + 32 * list_issuer.len() + 1 + 1 + 1 + 1 + 8;
+    }
+
+    pub fn get_len(&self) -> usize {
+        8 + 32 + 4 + 32 * self.list_issuer.len() + self.get_options_len()
     }
 
     pub fn get_add_issuer_len(&self) -> usize {
-        return 8 + 32 + 4 + 32 * self.list_issuer.len() + 32;
+        return 8 + 32 + 4 + 32 * self.list_issuer.len() + 32 + self.get_options_len();
     }
 
     pub fn get_remove_issuer_len(&self) -> usize {
-        return 8 + 32 + 4 + 32 * self.list_issuer.len() - 32;
+        return 8 + 32 + 4 + 32 * self.list_issuer.len() - 32 + self.get_options_len();
+    }
+
+    pub fn get_set_fee_schedule_len(&self, fee_schedule: &Option<FeeSchedule>) -> usize {
+        8 + 32 + 4 + 32 * self.list_issuer.len() + 1 + fee_schedule.as_ref().map_or(0, |f| f.get_len()) + 1 + self.get_baseline_limits_len() + 1 + 8
+    }
+
+    pub fn get_set_baseline_limits_len(&self, baseline_limits: &Option<BaselineLimits>) -> usize {
+        8 + 32 + 4 + 32 * self.list_issuer.len() + 1 + self.fee_schedule.as_ref().map_or(0, |f| f.get_len()) + 1 + 1 + baseline_limits.as_ref().map_or(0, |_| BaselineLimits::LEN) + 1 + 8
+    }
+
+    // Fee schedule, allow_unverified_recipients, baseline limits and travel rule threshold
+    fn get_options_len(&self) -> usize {
+        1 + self.fee_schedule.as_ref().map_or(0, |f| f.get_len()) + 1 + self.get_baseline_limits_len() + 1 + 8
+    }
+
+    fn get_baseline_limits_len(&self) -> usize {
+        1 + self.baseline_limits.as_ref().map_or(0, |_| BaselineLimits::LEN)
+    }
+
+    pub fn needs_travel_rule_record(&self, amount: u64) -> bool {
+        self.travel_rule_threshold.is_some_and(|threshold| amount >= threshold)
+    }
+
+    // Fee due by `owner` for a transfer of `amount`, 0 without fee schedule
+    pub fn get_fee(&self, amount: u64, owner: &Pubkey) -> u64 {
+        self.fee_schedule.as_ref().map_or(0, |f| f.get_fee(amount, owner))
+    }
+}
+
+// Caps on the outgoing transfers of each wrapped account over a sliding window, the two auth can't unlock them
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BaselineLimits {
+    pub duration: Duration,
+    pub max_amount: u64,       // In base units of the transferred mint
+    pub max_transactions: u64,
+}
+
+impl BaselineLimits {
+    pub const LEN: usize = Duration::LEN + 8 + 8;
+
+    // A zero duration would give the limit counters an empty time window
+    pub fn is_valid(&self) -> bool {
+        self.duration.get() > 0
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FeeSchedule {
+    pub fee_manager: Pubkey, // Can withdraw the collected fees
+    pub basis_points: u16,
+    pub min_fee: u64,        // In base units of the transferred mint
+    pub max_fee: u64,
+    pub exempt: Vec<Pubkey>, // Owners not paying any fee
+}
+
+impl FeeSchedule {
+    pub fn get_len(&self) -> usize {
+        32 + 2 + 8 + 8 + 4 + 32 * self.exempt.len()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.basis_points <= 10_000 && self.min_fee <= self.max_fee
+    }
+
+    pub fn get_fee(&self, amount: u64, owner: &Pubkey) -> u64 {
+        if self.exempt.contains(owner) {
+            return 0;
+        }
+        // basis_points <= 10_000 so the proportional fee fits in a u64, the min fee is capped to the amount as well
+        let fee = ((amount as u128) * (self.basis_points as u128) / 10_000) as u64;
+        fee.max(self.min_fee).min(self.max_fee).min(amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fee_schedule_get_fee() {
+        let exempt = Pubkey::new_unique();
+        let fee_schedule = FeeSchedule {
+            fee_manager: Pubkey::new_unique(),
+            basis_points: 50, // 0.5%
+            min_fee: 10,
+            max_fee: 1_000,
+            exempt: vec![exempt],
+        };
+        let owner = Pubkey::new_unique();
+        assert_eq!(fee_schedule.get_fee(10_000, &owner), 50);
+        assert_eq!(fee_schedule.get_fee(100, &owner), 10);
+        assert_eq!(fee_schedule.get_fee(1_000_000, &owner), 1_000);
+        assert_eq!(fee_schedule.get_fee(1_000_000, &exempt), 0);
+        assert_eq!(fee_schedule.get_fee(4, &owner), 4);
+        assert_eq!(fee_schedule.get_fee(0, &owner), 0);
+        assert_eq!(fee_schedule.get_len(), 32 + 2 + 8 + 8 + 4 + 32);
+    }
+
+    #[test]
+    fn baseline_limits_is_valid() {
+        let baseline_limits = |duration| BaselineLimits { duration, max_amount: 100, max_transactions: 10 };
+        assert!(baseline_limits(Duration::Days(1)).is_valid());
+        assert!(baseline_limits(Duration::Seconds(1)).is_valid());
+        assert!(!baseline_limits(Duration::Days(0)).is_valid());
+        assert!(!baseline_limits(Duration::Weeks(0)).is_valid());
     }
 }
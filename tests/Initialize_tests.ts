@@ -208,6 +208,7 @@ export async function initialize_two_auth(
   idendity: anchor.web3.PublicKey,
   approver: anchor.web3.PublicKey,
   wrapper_account: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey,
   two_auth_entity: anchor.web3.PublicKey,
   program: Program<HandmadeNaive>
 ): Promise<anchor.web3.PublicKey> {
@@ -215,6 +216,7 @@ export async function initialize_two_auth(
     [
       Buffer.from("two_auth"),
       wrapper_account.toBuffer(),
+      mint.toBuffer(),
       owner.publicKey.toBuffer(),
     ],
     program.programId
@@ -254,7 +256,10 @@ export async function initialize_two_auth(
         { deactivateForUserSpecificWhiteList: { whiteList: [] } },
         { always: {} },
       ],
+      rule: null,
       allowedIssuers: [approver],
+      priceMaxAge: null,
+      downgradeDelay: new anchor.BN(0),
     })
     .accountsPartial({
       wrapperAccount: wrapper_account,
@@ -263,6 +268,7 @@ export async function initialize_two_auth(
       payer: anchor.Wallet.local().publicKey,
       twoAuth: two_auth,
       idendity: idendity,
+      mint: mint,
       twoAuthEntity: two_auth_entity,
     })
     .signers([owner, anchor.Wallet.local().payer])
@@ -272,3 +278,181 @@ export async function initialize_two_auth(
 
   return two_auth;
 }
+
+export async function initialize_empty_two_auth(
+  owner: anchor.web3.Signer,
+  approver: anchor.web3.PublicKey,
+  wrapper_account: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+): Promise<anchor.web3.PublicKey> {
+  const [two_auth] = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("two_auth"),
+      wrapper_account.toBuffer(),
+      mint.toBuffer(),
+      owner.publicKey.toBuffer(),
+    ],
+    program.programId
+  );
+
+  const tx = await program.methods
+    .initializeTwoAuth(null)
+    .accountsPartial({
+      wrapperAccount: wrapper_account,
+      approver: approver,
+      owner: owner.publicKey,
+      payer: anchor.Wallet.local().publicKey,
+      twoAuth: two_auth,
+      mint: mint,
+      twoAuthEntity: null,
+    })
+    .signers([owner, anchor.Wallet.local().payer])
+    .rpc();
+
+  console.log("Init two_auth without policy tx", tx);
+
+  return two_auth;
+}
+
+export async function update_two_auth(
+  owner: anchor.web3.Signer,
+  approver: anchor.web3.PublicKey,
+  wrapper_account: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey,
+  two_auth_args: any,
+  two_auth_entity: anchor.web3.Signer | null,
+  old_two_auth_entity: anchor.web3.Signer | null,
+  program: Program<HandmadeNaive>
+) {
+  const tx = await program.methods
+    .updateTwoAuth(two_auth_args)
+    .accountsPartial({
+      wrapperAccount: wrapper_account,
+      approver: approver,
+      owner: owner.publicKey,
+      mint: mint,
+      twoAuthEntity: two_auth_entity ? two_auth_entity.publicKey : null,
+      oldTwoAuthEntity: old_two_auth_entity ? old_two_auth_entity.publicKey : null,
+    })
+    .signers([owner, two_auth_entity, old_two_auth_entity].filter((signer) => signer !== null))
+    .rpc();
+
+  console.log("Update two_auth tx", tx);
+}
+
+export async function set_baseline_limits(
+  wrapper_account: anchor.web3.PublicKey,
+  approver: anchor.web3.Signer,
+  baseline_limits: { days: number; max_amount: number; max_transactions: number } | null,
+  program: Program<HandmadeNaive>
+) {
+  const tx = await program.methods
+    .setBaselineLimits(
+      baseline_limits
+        ? {
+            duration: { days: [baseline_limits.days] },
+            maxAmount: new anchor.BN(baseline_limits.max_amount),
+            maxTransactions: new anchor.BN(baseline_limits.max_transactions),
+          }
+        : null
+    )
+    .accountsPartial({
+      wrapperAccount: wrapper_account,
+      payer: anchor.Wallet.local().publicKey,
+      approver: approver.publicKey,
+    })
+    .signers([approver, anchor.Wallet.local().payer])
+    .rpc();
+
+  console.log("Set baseline limits tx", tx);
+}
+
+export async function initialize_limit_counter(
+  wrapper_account: anchor.web3.PublicKey,
+  wrapped_account: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+) {
+  const [limit_counter] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("limit_counter"), wrapped_account.toBuffer()],
+    program.programId
+  );
+
+  const tx = await program.methods
+    .initializeLimitCounter()
+    .accountsPartial({
+      limitCounter: limit_counter,
+      wrappedAccount: wrapped_account,
+      wrapperAccount: wrapper_account,
+      payer: anchor.Wallet.local().publicKey,
+    })
+    .signers([anchor.Wallet.local().payer])
+    .rpc();
+
+  console.log("Init limit counter tx", tx);
+
+  return limit_counter;
+}
+
+export async function set_allow_unverified_recipients(
+  wrapper_account: anchor.web3.PublicKey,
+  approver: anchor.web3.Signer,
+  allow_unverified_recipients: boolean,
+  program: Program<HandmadeNaive>
+) {
+  const tx = await program.methods
+    .setAllowUnverifiedRecipients(allow_unverified_recipients)
+    .accountsPartial({
+      wrapperAccount: wrapper_account,
+      approver: approver.publicKey,
+    })
+    .signers([approver])
+    .rpc();
+
+  console.log("Set allow unverified recipients tx", tx);
+}
+
+export async function set_travel_rule_threshold(
+  wrapper_account: anchor.web3.PublicKey,
+  approver: anchor.web3.Signer,
+  threshold: number | null,
+  program: Program<HandmadeNaive>
+) {
+  const tx = await program.methods
+    .setTravelRuleThreshold(threshold === null ? null : new anchor.BN(threshold))
+    .accountsPartial({
+      wrapperAccount: wrapper_account,
+      approver: approver.publicKey,
+    })
+    .signers([approver])
+    .rpc();
+
+  console.log("Set travel rule threshold tx", tx);
+}
+
+export async function add_to_blocklist(
+  wrapper_account: anchor.web3.PublicKey,
+  approver: anchor.web3.Signer,
+  owner: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+) {
+  const [blocklist_entry] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("blocklist"), wrapper_account.toBuffer(), owner.toBuffer()],
+    program.programId
+  );
+
+  const tx = await program.methods
+    .addToBlocklist(owner)
+    .accountsPartial({
+      blocklistEntry: blocklist_entry,
+      wrapperAccount: wrapper_account,
+      approver: approver.publicKey,
+      payer: anchor.Wallet.local().publicKey,
+    })
+    .signers([approver, anchor.Wallet.local().payer])
+    .rpc();
+
+  console.log("Add to blocklist tx", tx);
+
+  return blocklist_entry;
+}
@@ -9,13 +9,20 @@ import {
   initialize_wrapper_token_holder,
   mint_tokens,
   initialize_two_auth,
+  initialize_empty_two_auth,
+  add_to_blocklist,
+  update_two_auth,
+  set_allow_unverified_recipients,
+  set_baseline_limits,
+  initialize_limit_counter,
+  set_travel_rule_threshold,
 } from "./Initialize_tests";
 import { TOKEN_PROGRAM_ID, transfer } from "@solana/spl-token";
-import { wrap_tokens } from "./wrapped_tokens_tests";
+import { deposit_for, transfer_out, unwrap_tokens, wrap_tokens } from "./wrapped_tokens_tests";
 import { min } from "bn.js";
 import { expect } from "chai";
-import { create_user_with_best_bump, sendTransaction, sleep } from "./utils";
-import { self_transfer_wtokens, send_transaction_buffer, transfer_sign_by_2_auth, transfer_with_partial_sig, transfer_wtokens } from "./transfer_tests";
+import { create_user_with_best_bump, expect_anchor_error, sendTransaction, sleep } from "./utils";
+import { approve_delegate, batch_transfer_wtokens, transfer_and_create_wtokens, cancel_scheduled_transfer, create_escrow, create_travel_rule_record, create_scheduled_transfer, create_trust_agreement, cross_wrapper_transfer, revoke_trust_agreement, execute_scheduled_transfer, refund_escrow, release_escrow, self_transfer_wtokens, send_transaction_buffer, transfer_from_wtokens, transfer_sign_by_2_auth, transfer_with_partial_sig, transfer_wtokens } from "./transfer_tests";
 import { issue_first_idendity } from "./idendity_tests";
 import fs from "fs";
 
@@ -269,6 +276,7 @@ describe("handmade_naive", async () => {
         user1_info.idendity,
         approver.publicKey,
         wrapper.wrapper_pda,
+        mint_info.mint,
         approver.publicKey,
         program
       );
@@ -514,6 +522,518 @@ describe("handmade_naive", async () => {
 
 
   })
+
+  it("Change Delegate Allowance", async () => {
+    const owner = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    const delegate = await create_verified_user(program, approver, issuer, mint_info, wrapper, 0);
+    const [allowance] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("allowance"), owner.wrapped_account.toBuffer(), delegate.user.publicKey.toBuffer()],
+      program.programId
+    );
+    const expires_at = Math.floor(Date.now() / 1000) + 3600;
+
+    await approve_delegate(delegate.user.publicKey, 4, expires_at, owner.user, owner.wrapped_account, program);
+    await approve_delegate(delegate.user.publicKey, 1, expires_at, owner.user, owner.wrapped_account, program);
+
+    let allowance_account = await program.account.allowance.fetch(allowance);
+    expect(allowance_account.amount.toNumber()).to.equal(1);
+
+    await expect_anchor_error(
+      transfer_from_wtokens(2, wrapper.wrapper_pda, delegate.user, owner.user.publicKey, owner.wrapped_account,
+        user2_info.user2.publicKey, user2_info.wrapped_account, owner.two_auth, program),
+      "AllowanceExceeded"
+    );
+
+    await transfer_from_wtokens(1, wrapper.wrapper_pda, delegate.user, owner.user.publicKey, owner.wrapped_account,
+      user2_info.user2.publicKey, user2_info.wrapped_account, owner.two_auth, program);
+
+    allowance_account = await program.account.allowance.fetch(allowance);
+    expect(allowance_account.amount.toNumber()).to.equal(0);
+    const owner_balance = await program.account.wrappedTokenAccount
+      .fetch(owner.wrapped_account)
+      .then((account) => account.amount.toNumber());
+    const user2_balance = await program.account.wrappedTokenAccount
+      .fetch(user2_info.wrapped_account)
+      .then((account) => account.amount.toNumber());
+    expect(owner_balance).to.equal(4);
+    expect(user2_balance).to.equal(USER2_BALANCE + 1);
+
+    USER2_BALANCE = USER2_BALANCE + 1;
+  });
+
+  it("Scheduled Transfer", async () => {
+    const owner = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    const now = Math.floor(Date.now() / 1000);
+    const get_balances = async () => [
+      await program.account.wrappedTokenAccount.fetch(owner.wrapped_account).then((account) => account.amount.toNumber()),
+      await program.account.wrappedTokenAccount.fetch(user2_info.wrapped_account).then((account) => account.amount.toNumber()),
+    ];
+    const execute = (scheduled_transfer: anchor.web3.PublicKey) =>
+      execute_scheduled_transfer(scheduled_transfer, wrapper.wrapper_pda, owner.user.publicKey, owner.wrapped_account,
+        user2_info.user2.publicKey, user2_info.wrapped_account, owner.two_auth, program);
+
+    // Not due yet, then cancelled
+    const not_due = await create_scheduled_transfer(0, 1, 3600, now + 3600, 2, owner.user,
+      owner.wrapped_account, user2_info.wrapped_account, program);
+    await expect_anchor_error(execute(not_due), "NotDue");
+    expect(await get_balances()).to.deep.equal([5, USER2_BALANCE]);
+    await cancel_scheduled_transfer(not_due, owner.user, program);
+    expect(await program.account.scheduledTransfer.fetchNullable(not_due)).to.be.null;
+
+    // Due with two missed slots: one execution, the missed ones are not caught up
+    const due = await create_scheduled_transfer(1, 1, 3600, now - 7200, 2, owner.user,
+      owner.wrapped_account, user2_info.wrapped_account, program);
+    await execute(due);
+    expect(await get_balances()).to.deep.equal([4, USER2_BALANCE + 1]);
+    const due_account = await program.account.scheduledTransfer.fetch(due);
+    expect(due_account.remainingCount).to.equal(1);
+    expect(due_account.nextExecution.toNumber()).to.be.greaterThan(now);
+    await expect_anchor_error(execute(due), "NotDue");
+    expect(await get_balances()).to.deep.equal([4, USER2_BALANCE + 1]);
+
+    // The last execution closes the scheduled transfer
+    const last = await create_scheduled_transfer(2, 2, 3600, now - 10, 1, owner.user,
+      owner.wrapped_account, user2_info.wrapped_account, program);
+    await execute(last);
+    expect(await get_balances()).to.deep.equal([2, USER2_BALANCE + 3]);
+    expect(await program.account.scheduledTransfer.fetchNullable(last)).to.be.null;
+
+    USER2_BALANCE = USER2_BALANCE + 3;
+  });
+
+  it("Escrow", async () => {
+    const sender = await create_verified_user(program, approver, issuer, mint_info, wrapper, 8);
+    const receiver = await create_verified_user(program, approver, issuer, mint_info, wrapper, 0);
+    const arbiter = anchor.web3.Keypair.generate();
+    const now = Math.floor(Date.now() / 1000);
+    const get_balances = async () => [
+      await program.account.wrappedTokenAccount.fetch(sender.wrapped_account).then((account) => account.amount.toNumber()),
+      await program.account.wrappedTokenAccount.fetch(receiver.wrapped_account).then((account) => account.amount.toNumber()),
+    ];
+    const create = (id: number, amount: number, arbiter_key: anchor.web3.PublicKey | null, deadline: number) =>
+      create_escrow(id, amount, arbiter_key, deadline, wrapper.wrapper_pda, sender.user, sender.wrapped_account,
+        receiver.wrapped_account, sender.two_auth, program);
+    const release = (escrow: anchor.web3.PublicKey, releaser: anchor.web3.Signer, arbiter_signer: anchor.web3.Signer | null) =>
+      release_escrow(escrow, wrapper.wrapper_pda, sender.user.publicKey, receiver.user.publicKey, receiver.wrapped_account,
+        releaser, arbiter_signer, program);
+
+    // Released by the sender
+    const by_sender = await create(0, 2, arbiter.publicKey, now + 3600);
+    expect(await get_balances()).to.deep.equal([6, 0]);
+    await release(by_sender, sender.user, null);
+    expect(await get_balances()).to.deep.equal([6, 2]);
+    expect(await program.account.escrow.fetchNullable(by_sender)).to.be.null;
+
+    // Released by the receiver along with the arbiter, the receiver alone is not enough
+    const by_arbiter = await create(1, 2, arbiter.publicKey, now + 3600);
+    await expect_anchor_error(release(by_arbiter, receiver.user, null), "WrongArbiter");
+    await release(by_arbiter, receiver.user, arbiter);
+    expect(await get_balances()).to.deep.equal([4, 4]);
+
+    // Refunded only from the deadline
+    const refunded = await create(2, 2, null, now + 5);
+    await expect_anchor_error(refund_escrow(refunded, sender.user, sender.wrapped_account, program), "DeadlineNotPassed");
+    expect(await get_balances()).to.deep.equal([2, 4]);
+    await sleep(8000);
+    await expect_anchor_error(release(refunded, sender.user, null), "DeadlinePassed");
+    await refund_escrow(refunded, sender.user, sender.wrapped_account, program);
+    expect(await get_balances()).to.deep.equal([4, 4]);
+    expect(await program.account.escrow.fetchNullable(refunded)).to.be.null;
+  });
+
+  it("Cross Wrapper Transfer", async () => {
+    // Second wrapper of the same mint, with its own approver and issuer
+    const approver2 = anchor.web3.Keypair.generate();
+    const issuer2 = anchor.web3.Keypair.generate();
+    const wrapper2_pda = await initialize_wrapper(anchor.Wallet.local().payer, issuer2, approver2, program);
+    const wrapper2_token_holder = await initialize_wrapper_token_holder(
+      approver2.publicKey,
+      anchor.Wallet.local().payer,
+      mint_info.mint,
+      wrapper2_pda,
+      program,
+      mint_info.token_program
+    );
+    const trust_agreement = await create_trust_agreement(approver, wrapper.wrapper_pda, approver2, wrapper2_pda, program);
+
+    const sender = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    const new_receiver = async (verified: boolean) => {
+      const receiver = anchor.web3.Keypair.generate();
+      if (verified) {
+        await issue_first_idendity(10000000, receiver, issuer2, approver2.publicKey, wrapper2_pda, program);
+      }
+      const wrapped_account = await initialize_wrapped_account(receiver, mint_info.mint, approver2.publicKey,
+        wrapper2_pda, program, mint_info.token_program);
+      return { receiver, wrapped_account };
+    };
+    const transfer = (amount: number, receiver: anchor.web3.Keypair, receiver_wrapped_account: anchor.web3.PublicKey) =>
+      cross_wrapper_transfer(
+        amount,
+        trust_agreement,
+        { wrapper_account: wrapper.wrapper_pda, approver: approver.publicKey, wrapper_token_account: wrapper.wrapper_token_holder },
+        { wrapper_account: wrapper2_pda, wrapper_token_account: wrapper2_token_holder },
+        sender.user,
+        sender.wrapped_account,
+        receiver.publicKey,
+        receiver_wrapped_account,
+        sender.two_auth,
+        mint_info.mint,
+        program,
+        mint_info.token_program
+      );
+    const get_backing = async (wrapper_pda: anchor.web3.PublicKey, wrapper_token_holder: anchor.web3.PublicKey) => {
+      const [treasury_pda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("treasury"), wrapper_pda.toBuffer(), mint_info.mint.toBuffer()],
+        program.programId
+      );
+      const treasury = await program.account.treasury.fetch(treasury_pda);
+      const held = await program.provider.connection.getTokenAccountBalance(wrapper_token_holder);
+      return {
+        held: Number(held.value.amount),
+        owed: treasury.userSupply.toNumber() + treasury.collectedFees.toNumber(),
+      };
+    };
+
+    // Under the agreement: the underlying tokens follow the wrapped amount, both wrappers stay backed
+    const receiver = await new_receiver(true);
+    const source_before = await get_backing(wrapper.wrapper_pda, wrapper.wrapper_token_holder);
+    await transfer(2, receiver.receiver, receiver.wrapped_account);
+
+    expect(await program.account.wrappedTokenAccount.fetch(sender.wrapped_account).then((account) => account.amount.toNumber())).to.equal(3);
+    expect(await program.account.wrappedTokenAccount.fetch(receiver.wrapped_account).then((account) => account.amount.toNumber())).to.equal(2);
+    const source_after = await get_backing(wrapper.wrapper_pda, wrapper.wrapper_token_holder);
+    const destination_after = await get_backing(wrapper2_pda, wrapper2_token_holder);
+    expect(source_after.held).to.equal(source_before.held - 2);
+    expect(source_after.owed).to.equal(source_before.owed - 2);
+    expect(source_after.held).to.be.at.least(source_after.owed);
+    expect(destination_after).to.deep.equal({ held: 2, owed: 2 });
+
+    // Receiver without identity on the destination wrapper
+    const unverified = await new_receiver(false);
+    await expect_anchor_error(transfer(1, unverified.receiver, unverified.wrapped_account), "AccountNotInitialized");
+
+    // Receiver on the blocklist of the destination wrapper
+    const blocked = await new_receiver(true);
+    await add_to_blocklist(wrapper2_pda, approver2, blocked.receiver.publicKey, program);
+    await expect_anchor_error(transfer(1, blocked.receiver, blocked.wrapped_account), "OwnerBlocked");
+
+    // Once revoked, the agreement can't be used anymore
+    await revoke_trust_agreement(trust_agreement, approver2, wrapper2_pda, program);
+    await expect_anchor_error(transfer(1, receiver.receiver, receiver.wrapped_account), "AccountNotInitialized");
+    expect(await program.account.wrappedTokenAccount.fetch(sender.wrapped_account).then((account) => account.amount.toNumber())).to.equal(3);
+    expect(await get_backing(wrapper2_pda, wrapper2_token_holder)).to.deep.equal({ held: 2, owed: 2 });
+  });
+
+  it("Batch Transfer", async () => {
+    const sender = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    const receiver = await create_verified_user(program, approver, issuer, mint_info, wrapper, 0);
+    const get_balance = (wrapped_account: anchor.web3.PublicKey) =>
+      program.account.wrappedTokenAccount.fetch(wrapped_account).then((account) => account.amount.toNumber());
+    const destinations = [
+      { owner: user2_info.user2.publicKey, wrapped_account: user2_info.wrapped_account },
+      { owner: receiver.user.publicKey, wrapped_account: receiver.wrapped_account },
+    ];
+    const batch = (amounts: number[], batch_destinations = destinations) =>
+      batch_transfer_wtokens(amounts, wrapper.wrapper_pda, sender.user, sender.wrapped_account, batch_destinations,
+        sender.two_auth, null, program);
+
+    // Each destination receives its own amount
+    await batch([2, 1]);
+    expect(await get_balance(sender.wrapped_account)).to.equal(2);
+    expect(await get_balance(user2_info.wrapped_account)).to.equal(USER2_BALANCE + 2);
+    expect(await get_balance(receiver.wrapped_account)).to.equal(1);
+    USER2_BALANCE = USER2_BALANCE + 2;
+
+    // The total is checked against the balance, and a destination can't be paid twice
+    await expect_anchor_error(batch([2, 1]), "InsufficientFunds");
+    await expect_anchor_error(batch([1, 1], [destinations[1], destinations[1]]), "InvalidBatchDestination");
+    await expect_anchor_error(batch([1]), "InvalidBatchAccounts");
+    expect(await get_balance(sender.wrapped_account)).to.equal(2);
+    expect(await get_balance(receiver.wrapped_account)).to.equal(1);
+  });
+
+  it("Batch Transfer Two Auth", async () => {
+    const two_auth_entity = anchor.web3.Keypair.generate();
+    const sender = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    await update_two_auth(sender.user, approver.publicKey, wrapper.wrapper_pda, mint_info.mint, {
+      functions: [
+        {
+          transactionCounterWithTimeWindow: {
+            max: new anchor.BN(2),
+            window: { duration: { days: [1] }, lastValueTime: new anchor.BN(0), window: [], startIndex: 0 },
+          },
+        },
+      ],
+      rule: null,
+      allowedIssuers: [],
+      priceMaxAge: null,
+      downgradeDelay: new anchor.BN(0),
+    }, two_auth_entity, null, program);
+    const receivers = [
+      await create_verified_user(program, approver, issuer, mint_info, wrapper, 0),
+      await create_verified_user(program, approver, issuer, mint_info, wrapper, 0),
+    ];
+    const destinations = [
+      { owner: user2_info.user2.publicKey, wrapped_account: user2_info.wrapped_account },
+      ...receivers.map((receiver) => ({ owner: receiver.user.publicKey, wrapped_account: receiver.wrapped_account })),
+    ];
+    const batch = (amounts: number[], two_auth_signer: anchor.web3.Signer | null) =>
+      batch_transfer_wtokens(amounts, wrapper.wrapper_pda, sender.user, sender.wrapped_account,
+        destinations.slice(0, amounts.length), sender.two_auth, two_auth_signer, program);
+    const get_balance = (wrapped_account: anchor.web3.PublicKey) =>
+      program.account.wrappedTokenAccount.fetch(wrapped_account).then((account) => account.amount.toNumber());
+
+    // Each payment of the batch counts as a transfer: max + 1 payments need the two auth
+    await expect_anchor_error(batch([1, 1, 1], null), "NeedTwoAuthApproval");
+    await batch([1, 1], null);
+    await expect_anchor_error(batch([1], null), "NeedTwoAuthApproval");
+    await batch([1, 1, 1], two_auth_entity);
+
+    expect(await get_balance(sender.wrapped_account)).to.equal(0);
+    expect(await get_balance(user2_info.wrapped_account)).to.equal(USER2_BALANCE + 2);
+    expect(await get_balance(receivers[0].wrapped_account)).to.equal(2);
+    expect(await get_balance(receivers[1].wrapped_account)).to.equal(1);
+    USER2_BALANCE = USER2_BALANCE + 2;
+  });
+
+  it("Unwrap Tokens", async () => {
+    const owner = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    const [treasury] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), wrapper.wrapper_pda.toBuffer(), mint_info.mint.toBuffer()],
+      program.programId
+    );
+    const get_balances = async () => [
+      await program.account.wrappedTokenAccount.fetch(owner.wrapped_account).then((account) => account.amount.toNumber()),
+      await program.provider.connection.getTokenAccountBalance(owner.token_account).then((balance) => Number(balance.value.amount)),
+    ];
+    const unwrap = (amount: number, decimals: number = mint_info.decimals) =>
+      unwrap_tokens(amount, decimals, wrapper.wrapper_pda, approver.publicKey, owner.user, owner.token_account,
+        mint_info.mint, wrapper.wrapper_token_holder, program, mint_info.token_program);
+
+    // The underlying tokens go back to the owner and leave the treasury supply
+    const supply_before = await program.account.treasury.fetch(treasury).then((account) => account.userSupply.toNumber());
+    await unwrap(2);
+    expect(await get_balances()).to.deep.equal([3, 2]);
+    const supply_after = await program.account.treasury.fetch(treasury).then((account) => account.userSupply.toNumber());
+    expect(supply_after).to.equal(supply_before - 2);
+
+    await expect_anchor_error(unwrap(1, mint_info.decimals + 1), "InvalidDecimals");
+    await expect_anchor_error(unwrap(4), "InsufficientFunds");
+    expect(await get_balances()).to.deep.equal([3, 2]);
+  });
+
+  it("Transfer Out", async () => {
+    const owner = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    const verified = await create_verified_user(program, approver, issuer, mint_info, wrapper, 0);
+    const unverified = anchor.web3.Keypair.generate();
+    const unverified_token_account = await create_spl_token_account(
+      anchor.Wallet.local().payer,
+      unverified.publicKey,
+      mint_info.mint,
+      mint_info.token_program
+    );
+    const get_token_balance = (token_account: anchor.web3.PublicKey) =>
+      program.provider.connection.getTokenAccountBalance(token_account).then((balance) => Number(balance.value.amount));
+    const send_out = (amount: number, destination_token_account: anchor.web3.PublicKey, idendity_receiver: anchor.web3.PublicKey | null) =>
+      transfer_out(amount, wrapper.wrapper_pda, approver.publicKey, owner.user, destination_token_account, idendity_receiver,
+        mint_info.mint, wrapper.wrapper_token_holder, program, mint_info.token_program);
+
+    // To the token account of a verified user
+    await send_out(2, verified.token_account, verified.idendity);
+    expect(await get_token_balance(verified.token_account)).to.equal(2);
+
+    // Unverified recipients only when the wrapper allows them
+    await expect_anchor_error(send_out(1, unverified_token_account, null), "UnverifiedRecipient");
+    expect(await get_token_balance(unverified_token_account)).to.equal(0);
+    await set_allow_unverified_recipients(wrapper.wrapper_pda, approver, true, program);
+    try {
+      await send_out(1, unverified_token_account, null);
+    } finally {
+      await set_allow_unverified_recipients(wrapper.wrapper_pda, approver, false, program);
+    }
+    expect(await get_token_balance(unverified_token_account)).to.equal(1);
+
+    await expect_anchor_error(send_out(3, verified.token_account, verified.idendity), "InsufficientFunds");
+    expect(
+      await program.account.wrappedTokenAccount.fetch(owner.wrapped_account).then((account) => account.amount.toNumber())
+    ).to.equal(2);
+  });
+
+  it("Deposit For", async () => {
+    const payer = await create_verified_user(program, approver, issuer, mint_info, wrapper, 0);
+    const recipient = await create_verified_user(program, approver, issuer, mint_info, wrapper, 0);
+    await mint_tokens(3, anchor.Wallet.local().payer, mint_info.mint, payer.token_account, mint_info.mintAuthority,
+      mint_info.token_program);
+    const deposit = (amount: number, recipient_owner: anchor.web3.PublicKey) =>
+      deposit_for(amount, mint_info.decimals, wrapper.wrapper_pda, approver.publicKey, payer.user, payer.token_account,
+        recipient_owner, mint_info.mint, wrapper.wrapper_token_holder, program, mint_info.token_program);
+    const get_payer_balance = () =>
+      program.provider.connection.getTokenAccountBalance(payer.token_account).then((balance) => Number(balance.value.amount));
+
+    // The payer's tokens are wrapped into the recipient's account
+    await deposit(2, recipient.user.publicKey);
+    expect(
+      await program.account.wrappedTokenAccount.fetch(recipient.wrapped_account).then((account) => account.amount.toNumber())
+    ).to.equal(2);
+    expect(
+      await program.account.wrappedTokenAccount.fetch(payer.wrapped_account).then((account) => account.amount.toNumber())
+    ).to.equal(0);
+    expect(await get_payer_balance()).to.equal(1);
+
+    // The recipient needs an identity and must not be blocked
+    const unverified = anchor.web3.Keypair.generate();
+    await initialize_wrapped_account(unverified, mint_info.mint, approver.publicKey, wrapper.wrapper_pda, program,
+      mint_info.token_program);
+    await expect_anchor_error(deposit(1, unverified.publicKey), "AccountNotInitialized");
+    await add_to_blocklist(wrapper.wrapper_pda, approver, recipient.user.publicKey, program);
+    await expect_anchor_error(deposit(1, recipient.user.publicKey), "OwnerBlocked");
+    expect(await get_payer_balance()).to.equal(1);
+  });
+
+  it("Transfer And Create", async () => {
+    const sender = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    const receiver = anchor.web3.Keypair.generate();
+    await issue_first_idendity(10000000, receiver, issuer, approver.publicKey, wrapper.wrapper_pda, program);
+    const [receiver_wrapped_account] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("wrapped_token"), wrapper.wrapper_pda.toBuffer(), mint_info.mint.toBuffer(), receiver.publicKey.toBuffer()],
+      program.programId
+    );
+    const send = (amount: number, destination_owner: anchor.web3.PublicKey) =>
+      transfer_and_create_wtokens(amount, wrapper.wrapper_pda, sender.user, sender.wrapped_account, destination_owner,
+        sender.two_auth, null, program);
+    const get_balance = (wrapped_account: anchor.web3.PublicKey) =>
+      program.account.wrappedTokenAccount.fetch(wrapped_account).then((account) => account.amount.toNumber());
+
+    // The first payment opens the receiver's wrapped account
+    await send(2, receiver.publicKey);
+    const receiver_account = await program.account.wrappedTokenAccount.fetch(receiver_wrapped_account);
+    expect(receiver_account.owner.toBase58()).to.equal(receiver.publicKey.toBase58());
+    expect(receiver_account.mint.toBase58()).to.equal(mint_info.mint.toBase58());
+    expect(receiver_account.amount.toNumber()).to.equal(2);
+    expect(await get_balance(sender.wrapped_account)).to.equal(3);
+
+    // The account can't be opened twice, further payments go through transfer
+    let reopened = true;
+    try {
+      await send(1, receiver.publicKey);
+    } catch (error) {
+      reopened = false;
+    }
+    expect(reopened).to.be.false;
+
+    // The receiver needs an identity
+    await expect_anchor_error(send(1, anchor.web3.Keypair.generate().publicKey), "AccountNotInitialized");
+    expect(await get_balance(sender.wrapped_account)).to.equal(3);
+    expect(await get_balance(receiver_wrapped_account)).to.equal(2);
+  });
+
+  it("Baseline Limits", async () => {
+    const owner = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    const send = (amount: number, limit_counter: anchor.web3.PublicKey | null) =>
+      transfer_wtokens(amount, wrapper.wrapper_pda, owner.user, owner.wrapped_account, user2_info.user2.publicKey,
+        user2_info.wrapped_account, owner.two_auth, null, program, null, limit_counter);
+
+    await expect_anchor_error(
+      set_baseline_limits(wrapper.wrapper_pda, approver, { days: 0, max_amount: 4, max_transactions: 2 }, program),
+      "InvalidBaselineLimits"
+    );
+
+    // The limits apply to every wrapped account of the wrapper, they are removed at the end
+    await set_baseline_limits(wrapper.wrapper_pda, approver, { days: 1, max_amount: 4, max_transactions: 2 }, program);
+    try {
+      await expect_anchor_error(send(1, null), "MissingLimitCounter");
+      const limit_counter = await initialize_limit_counter(wrapper.wrapper_pda, owner.wrapped_account, program);
+
+      await send(2, limit_counter);
+      await expect_anchor_error(send(3, limit_counter), "AmountLimitExceeded");
+      await send(1, limit_counter);
+      await expect_anchor_error(send(1, limit_counter), "TransactionLimitExceeded");
+    } finally {
+      await set_baseline_limits(wrapper.wrapper_pda, approver, null, program);
+    }
+
+    expect(
+      await program.account.wrappedTokenAccount.fetch(owner.wrapped_account).then((account) => account.amount.toNumber())
+    ).to.equal(2);
+    expect(
+      await program.account.wrappedTokenAccount.fetch(user2_info.wrapped_account).then((account) => account.amount.toNumber())
+    ).to.equal(USER2_BALANCE + 3);
+    USER2_BALANCE = USER2_BALANCE + 3;
+
+    // Without limits, no counter is needed
+    await send(1, null);
+    USER2_BALANCE = USER2_BALANCE + 1;
+  });
+
+  it("Travel Rule", async () => {
+    const owner = await create_verified_user(program, approver, issuer, mint_info, wrapper, 5);
+    const send = (amount: number, travel_rule_record: anchor.web3.PublicKey | null) =>
+      transfer_wtokens(amount, wrapper.wrapper_pda, owner.user, owner.wrapped_account, user2_info.user2.publicKey,
+        user2_info.wrapped_account, owner.two_auth, null, program, null, null, travel_rule_record);
+
+    // The threshold applies to every wrapped account of the wrapper, it is removed at the end
+    await set_travel_rule_threshold(wrapper.wrapper_pda, approver, 2, program);
+    try {
+      await send(1, null);
+      await expect_anchor_error(send(2, null), "RecordRequired");
+      await expect_anchor_error(
+        batch_transfer_wtokens([2], wrapper.wrapper_pda, owner.user, owner.wrapped_account,
+          [{ owner: user2_info.user2.publicKey, wrapped_account: user2_info.wrapped_account }], owner.two_auth, null, program),
+        "BatchAboveThreshold"
+      );
+
+      // The record attested by the issuer is bound to one transfer and closed by it
+      const travel_rule_record = await create_travel_rule_record(0, user2_info.wrapped_account, 2, Array(32).fill(1),
+        wrapper.wrapper_pda, owner.user, owner.wrapped_account, issuer, program);
+      await expect_anchor_error(send(3, travel_rule_record), "RecordMismatch");
+      await send(2, travel_rule_record);
+      expect(await program.account.travelRuleRecord.fetchNullable(travel_rule_record)).to.be.null;
+    } finally {
+      await set_travel_rule_threshold(wrapper.wrapper_pda, approver, null, program);
+    }
+
+    expect(
+      await program.account.wrappedTokenAccount.fetch(owner.wrapped_account).then((account) => account.amount.toNumber())
+    ).to.equal(2);
+    expect(
+      await program.account.wrappedTokenAccount.fetch(user2_info.wrapped_account).then((account) => account.amount.toNumber())
+    ).to.equal(USER2_BALANCE + 3);
+    USER2_BALANCE = USER2_BALANCE + 3;
+  });
+
+  it("Nullifier", async () => {
+    const nullifier = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+    const [nullifier_account] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("nullifier"), issuer.publicKey.toBuffer(), Buffer.from(nullifier)],
+      program.programId
+    );
+    const get_idendity = (owner: anchor.web3.Keypair) =>
+      anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("identity"), owner.publicKey.toBuffer()], program.programId)[0];
+
+    // The first identity of the subject binds the nullifier for the issuer
+    const first = anchor.web3.Keypair.generate();
+    await issue_first_idendity(10000000, first, issuer, approver.publicKey, wrapper.wrapper_pda, program, nullifier);
+    const bound = await program.account.nullifierAccount.fetch(nullifier_account);
+    expect(bound.issuer.toBase58()).to.equal(issuer.publicKey.toBase58());
+    expect(bound.nullifier).to.deep.equal(nullifier);
+    expect(bound.idendity.toBase58()).to.equal(get_idendity(first).toBase58());
+
+    // A second identity of the same subject is refused while the first one isn't recovered
+    const second = anchor.web3.Keypair.generate();
+    await expect_anchor_error(
+      issue_first_idendity(10000000, second, issuer, approver.publicKey, wrapper.wrapper_pda, program, nullifier),
+      "MissingPreviousIdendity"
+    );
+    await expect_anchor_error(
+      issue_first_idendity(10000000, second, issuer, approver.publicKey, wrapper.wrapper_pda, program, nullifier,
+        get_idendity(first)),
+      "NullifierAlreadyUsed"
+    );
+    expect(await program.account.idAccount.fetchNullable(get_idendity(second))).to.be.null;
+    expect(
+      (await program.account.nullifierAccount.fetch(nullifier_account)).idendity.toBase58()
+    ).to.equal(get_idendity(first).toBase58());
+  });
 });
 
 interface InitReturn {
@@ -636,3 +1156,78 @@ async function init(
     issuer: issuer,
   };
 }
+
+interface VerifiedUser {
+  user: anchor.web3.Keypair;
+  idendity: anchor.web3.PublicKey;
+  token_account: anchor.web3.PublicKey;
+  wrapped_account: anchor.web3.PublicKey;
+  two_auth: anchor.web3.PublicKey;
+}
+
+// User with an identity, a wrapped account holding `wtokens` and a two auth account without policy
+async function create_verified_user(
+  program: Program<HandmadeNaive>,
+  approver: anchor.web3.Keypair,
+  issuer: anchor.web3.Keypair,
+  mint_info: InitReturn["mint_info"],
+  wrapper: InitReturn["wrapper"],
+  wtokens: number
+): Promise<VerifiedUser> {
+  const user = await create_user_with_best_bump(program, mint_info.mint);
+  console.log("[Pk] user", user.publicKey.toBase58());
+  await sendTransaction(anchor.Wallet.local().payer, user.publicKey, 10000000);
+
+  await issue_first_idendity(10000000, user, issuer, approver.publicKey, wrapper.wrapper_pda, program);
+  const [idendity] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("identity"), user.publicKey.toBuffer()],
+    program.programId
+  );
+
+  const token_account = await create_spl_token_account(
+    anchor.Wallet.local().payer,
+    user.publicKey,
+    mint_info.mint,
+    mint_info.token_program
+  );
+  const wrapped_account = await initialize_wrapped_account(
+    user,
+    mint_info.mint,
+    approver.publicKey,
+    wrapper.wrapper_pda,
+    program,
+    mint_info.token_program
+  );
+  const two_auth = await initialize_empty_two_auth(
+    user,
+    approver.publicKey,
+    wrapper.wrapper_pda,
+    mint_info.mint,
+    program
+  );
+
+  if (wtokens > 0) {
+    await mint_tokens(
+      wtokens,
+      anchor.Wallet.local().payer,
+      mint_info.mint,
+      token_account,
+      mint_info.mintAuthority,
+      mint_info.token_program
+    );
+    await wrap_tokens(
+      wtokens,
+      mint_info.decimals,
+      wrapper.wrapper_pda,
+      approver.publicKey,
+      user,
+      token_account,
+      mint_info.mint,
+      wrapper.wrapper_token_holder,
+      program,
+      mint_info.token_program
+    );
+  }
+
+  return { user, idendity, token_account, wrapped_account, two_auth };
+}
@@ -8,7 +8,9 @@ export async function issue_first_idendity(
   issuer: anchor.web3.Signer,
   approver: anchor.web3.PublicKey,
   wrapper: anchor.web3.PublicKey,
-  program: Program<HandmadeNaive>
+  program: Program<HandmadeNaive>,
+  nullifier: number[] | null = null,
+  previous_idendity: anchor.web3.PublicKey | null = null
 ) {
   const [idendity, bump] = await anchor.web3.PublicKey.findProgramAddressSync(
     [Buffer.from("identity"), owner.publicKey.toBuffer()],
@@ -17,8 +19,15 @@ export async function issue_first_idendity(
 
   console.log(`[Pk] Issue  Idendity : ${idendity}`);
 
+  const nullifier_account = nullifier
+    ? anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("nullifier"), issuer.publicKey.toBuffer(), Buffer.from(nullifier)],
+        program.programId
+      )[0]
+    : null;
+
   const tx = await program.methods
-    .initializeId(new anchor.BN(validity_duration))
+    .initializeId(new anchor.BN(validity_duration), nullifier)
     .accountsPartial({
       approver: approver,
       wrapperAccount: wrapper,
@@ -26,6 +35,8 @@ export async function issue_first_idendity(
       owner: owner.publicKey,
       payer: anchor.Wallet.local().publicKey,
       idendity: idendity,
+      nullifierAccount: nullifier_account,
+      previousIdendity: previous_idendity,
     })
     .signers([issuer, anchor.Wallet.local().payer, owner])
     .rpc();
@@ -1,6 +1,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { HandmadeNaive } from "../target/types/handmade_naive";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 
 export async function transfer_wtokens(
   amount: number,
@@ -11,10 +12,13 @@ export async function transfer_wtokens(
   destination_wrapped_account: anchor.web3.PublicKey,
   two_auth: anchor.web3.PublicKey,
   two_auth_signer: anchor.web3.Signer | null,
-  program: Program<HandmadeNaive>
+  program: Program<HandmadeNaive>,
+  memo: string | null = null,
+  limit_counter: anchor.web3.PublicKey | null = null,
+  travel_rule_record: anchor.web3.PublicKey | null = null
 ) {
   const instruction = await program.methods
-    .transfer(new anchor.BN(amount))
+    .transfer(new anchor.BN(amount), memo)
     .accountsPartial({
       sourceOwner: source_owner.publicKey,
       destinationOwner: destination_owner,
@@ -23,6 +27,8 @@ export async function transfer_wtokens(
       twoAuthSigner: two_auth_signer ? two_auth_signer.publicKey : null,
       twoAuth: two_auth,
       wrapperAccount: wrapper_account,
+      limitCounter: limit_counter,
+      travelRuleRecord: travel_rule_record,
     })
     .instruction();
 
@@ -37,6 +43,173 @@ export async function transfer_wtokens(
   console.log(`Transfer (wrapped) of ${amount} tx : ${txSig}`);
 }
 
+export async function transfer_and_create_wtokens(
+  amount: number,
+  wrapper_account: anchor.web3.PublicKey,
+  source_owner: anchor.web3.Signer,
+  source_wrapped_account: anchor.web3.PublicKey,
+  destination_owner: anchor.web3.PublicKey,
+  two_auth: anchor.web3.PublicKey,
+  two_auth_signer: anchor.web3.Signer | null,
+  program: Program<HandmadeNaive>,
+  memo: string | null = null
+) {
+  const instruction = await program.methods
+    .transferAndCreate(new anchor.BN(amount), memo)
+    .accountsPartial({
+      sourceOwner: source_owner.publicKey,
+      destinationOwner: destination_owner,
+      sourceWrappedAccount: source_wrapped_account,
+      twoAuthSigner: two_auth_signer ? two_auth_signer.publicKey : null,
+      twoAuth: two_auth,
+      wrapperAccount: wrapper_account,
+    })
+    .instruction();
+
+  const transaction = new anchor.web3.Transaction().add(instruction);
+
+  const txSig = await anchor.web3.sendAndConfirmTransaction(
+    anchor.getProvider().connection,
+    transaction,
+    two_auth_signer ? [source_owner, two_auth_signer] : [source_owner]
+  );
+
+  console.log(`Transfer (wrapped) with account creation of ${amount} tx : ${txSig}`);
+}
+
+export async function batch_transfer_wtokens(
+  amounts: number[],
+  wrapper_account: anchor.web3.PublicKey,
+  source_owner: anchor.web3.Signer,
+  source_wrapped_account: anchor.web3.PublicKey,
+  destinations: { owner: anchor.web3.PublicKey, wrapped_account: anchor.web3.PublicKey }[],
+  two_auth: anchor.web3.PublicKey,
+  two_auth_signer: anchor.web3.Signer | null,
+  program: Program<HandmadeNaive>,
+  memo: string | null = null
+) {
+  const remaining_accounts = destinations.flatMap((destination) => {
+    const [idendity] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("identity"), destination.owner.toBuffer()],
+      program.programId
+    );
+    const [blocklist_entry] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("blocklist"), wrapper_account.toBuffer(), destination.owner.toBuffer()],
+      program.programId
+    );
+    return [
+      { pubkey: destination.wrapped_account, isWritable: true, isSigner: false },
+      { pubkey: destination.owner, isWritable: false, isSigner: false },
+      { pubkey: idendity, isWritable: false, isSigner: false },
+      { pubkey: blocklist_entry, isWritable: false, isSigner: false },
+    ];
+  });
+
+  const instruction = await program.methods
+    .batchTransfer(amounts.map((amount) => new anchor.BN(amount)), memo)
+    .accountsPartial({
+      sourceOwner: source_owner.publicKey,
+      sourceWrappedAccount: source_wrapped_account,
+      twoAuthSigner: two_auth_signer ? two_auth_signer.publicKey : null,
+      twoAuth: two_auth,
+      wrapperAccount: wrapper_account,
+    })
+    .remainingAccounts(remaining_accounts)
+    .instruction();
+
+  const transaction = new anchor.web3.Transaction().add(instruction);
+
+  const txSig = await anchor.web3.sendAndConfirmTransaction(
+    anchor.getProvider().connection,
+    transaction,
+    two_auth_signer ? [source_owner, two_auth_signer] : [source_owner]
+  );
+
+  console.log(`Batch transfer (wrapped) of ${amounts.length} amounts tx : ${txSig}`);
+}
+
+export async function create_travel_rule_record(
+  id: number,
+  destination: anchor.web3.PublicKey,
+  amount: number,
+  data_hash: number[],
+  wrapper_account: anchor.web3.PublicKey,
+  source_owner: anchor.web3.Signer,
+  source_wrapped_account: anchor.web3.PublicKey,
+  issuer: anchor.web3.Signer,
+  program: Program<HandmadeNaive>
+) {
+  const [travel_rule_record] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("travel_rule"), source_wrapped_account.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
+  const txSig = await program.methods
+    .createTravelRuleRecord(new anchor.BN(id), destination, new anchor.BN(amount), data_hash)
+    .accountsPartial({
+      travelRuleRecord: travel_rule_record,
+      sourceWrappedAccount: source_wrapped_account,
+      sourceOwner: source_owner.publicKey,
+      issuer: issuer.publicKey,
+      wrapperAccount: wrapper_account,
+    })
+    .signers([source_owner, issuer])
+    .rpc();
+
+  console.log(`Travel rule record of ${amount} tx : ${txSig}`);
+
+  return travel_rule_record;
+}
+
+export async function approve_delegate(
+  delegate: anchor.web3.PublicKey,
+  amount: number,
+  expires_at: number,
+  owner: anchor.web3.Signer,
+  wrapped_account: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+) {
+  const txSig = await program.methods
+    .approveDelegate(delegate, new anchor.BN(amount), new anchor.BN(expires_at))
+    .accountsPartial({
+      owner: owner.publicKey,
+      wrappedAccount: wrapped_account,
+    })
+    .signers([owner])
+    .rpc();
+
+  console.log(`Approve delegate of ${amount} tx : ${txSig}`);
+}
+
+export async function transfer_from_wtokens(
+  amount: number,
+  wrapper_account: anchor.web3.PublicKey,
+  delegate: anchor.web3.Signer,
+  source_owner: anchor.web3.PublicKey,
+  source_wrapped_account: anchor.web3.PublicKey,
+  destination_owner: anchor.web3.PublicKey,
+  destination_wrapped_account: anchor.web3.PublicKey,
+  two_auth: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+) {
+  const txSig = await program.methods
+    .transferFrom(new anchor.BN(amount), null)
+    .accountsPartial({
+      delegate: delegate.publicKey,
+      sourceOwner: source_owner,
+      destinationOwner: destination_owner,
+      sourceWrappedAccount: source_wrapped_account,
+      destinationWrappedAccount: destination_wrapped_account,
+      twoAuthSigner: null,
+      twoAuth: two_auth,
+      wrapperAccount: wrapper_account,
+    })
+    .signers([delegate])
+    .rpc();
+
+  console.log(`Transfer from (wrapped) of ${amount} tx : ${txSig}`);
+}
+
 export async function self_transfer_wtokens(
   amount: number,
   wrapper_account: anchor.web3.PublicKey,
@@ -45,7 +218,7 @@ export async function self_transfer_wtokens(
   program: Program<HandmadeNaive>
 ) {
   const instruction = await program.methods
-    .transfer(new anchor.BN(amount))
+    .transfer(new anchor.BN(amount), null)
     .accountsPartial({
       sourceOwner: source_owner.publicKey,
       destinationOwner: source_owner.publicKey,
@@ -84,7 +257,7 @@ export async function transfer_with_partial_sig(
   lastValidBlockHeight: number;
 }]> {
   const instruction = await program.methods
-    .transfer(new anchor.BN(amount))
+    .transfer(new anchor.BN(amount), null)
     .accountsPartial({
       sourceOwner: source_owner.publicKey,
       destinationOwner: destination_owner,
@@ -153,4 +326,268 @@ export async function send_transaction_buffer(
   console.log(`Transfer (wrapped) raw tx : ${txSig}`);
 
   return txSig;
-}
\ No newline at end of file
+}
+export async function create_scheduled_transfer(
+  id: number,
+  amount: number,
+  interval: number,
+  first_execution: number,
+  count: number,
+  owner: anchor.web3.Signer,
+  source_wrapped_account: anchor.web3.PublicKey,
+  destination_wrapped_account: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+): Promise<anchor.web3.PublicKey> {
+  const [scheduled_transfer] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("scheduled_transfer"), source_wrapped_account.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
+  const txSig = await program.methods
+    .createScheduledTransfer(
+      new anchor.BN(id),
+      new anchor.BN(amount),
+      new anchor.BN(interval),
+      new anchor.BN(first_execution),
+      count,
+      null
+    )
+    .accountsPartial({
+      scheduledTransfer: scheduled_transfer,
+      sourceWrappedAccount: source_wrapped_account,
+      destinationWrappedAccount: destination_wrapped_account,
+      owner: owner.publicKey,
+    })
+    .signers([owner])
+    .rpc();
+
+  console.log(`Scheduled transfer of ${amount} x ${count} tx : ${txSig}`);
+
+  return scheduled_transfer;
+}
+
+export async function execute_scheduled_transfer(
+  scheduled_transfer: anchor.web3.PublicKey,
+  wrapper_account: anchor.web3.PublicKey,
+  source_owner: anchor.web3.PublicKey,
+  source_wrapped_account: anchor.web3.PublicKey,
+  destination_owner: anchor.web3.PublicKey,
+  destination_wrapped_account: anchor.web3.PublicKey,
+  two_auth: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+) {
+  const txSig = await program.methods
+    .executeScheduledTransfer()
+    .accountsPartial({
+      scheduledTransfer: scheduled_transfer,
+      sourceOwner: source_owner,
+      destinationOwner: destination_owner,
+      sourceWrappedAccount: source_wrapped_account,
+      destinationWrappedAccount: destination_wrapped_account,
+      twoAuthSigner: null,
+      twoAuth: two_auth,
+      wrapperAccount: wrapper_account,
+    })
+    .rpc();
+
+  console.log(`Execute scheduled transfer tx : ${txSig}`);
+}
+
+export async function cancel_scheduled_transfer(
+  scheduled_transfer: anchor.web3.PublicKey,
+  owner: anchor.web3.Signer,
+  program: Program<HandmadeNaive>
+) {
+  const txSig = await program.methods
+    .cancelScheduledTransfer()
+    .accountsPartial({
+      scheduledTransfer: scheduled_transfer,
+      owner: owner.publicKey,
+    })
+    .signers([owner])
+    .rpc();
+
+  console.log(`Cancel scheduled transfer tx : ${txSig}`);
+}
+
+export async function create_escrow(
+  id: number,
+  amount: number,
+  arbiter: anchor.web3.PublicKey | null,
+  deadline: number,
+  wrapper_account: anchor.web3.PublicKey,
+  source_owner: anchor.web3.Signer,
+  source_wrapped_account: anchor.web3.PublicKey,
+  destination_wrapped_account: anchor.web3.PublicKey,
+  two_auth: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+): Promise<anchor.web3.PublicKey> {
+  const [escrow] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("escrow"), source_wrapped_account.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
+  const txSig = await program.methods
+    .createEscrow(new anchor.BN(id), new anchor.BN(amount), arbiter, new anchor.BN(deadline))
+    .accountsPartial({
+      escrow: escrow,
+      sourceOwner: source_owner.publicKey,
+      sourceWrappedAccount: source_wrapped_account,
+      destinationWrappedAccount: destination_wrapped_account,
+      twoAuthSigner: null,
+      twoAuth: two_auth,
+      wrapperAccount: wrapper_account,
+    })
+    .signers([source_owner])
+    .rpc();
+
+  console.log(`Escrow of ${amount} tx : ${txSig}`);
+
+  return escrow;
+}
+
+export async function release_escrow(
+  escrow: anchor.web3.PublicKey,
+  wrapper_account: anchor.web3.PublicKey,
+  sender: anchor.web3.PublicKey,
+  destination_owner: anchor.web3.PublicKey,
+  destination_wrapped_account: anchor.web3.PublicKey,
+  releaser: anchor.web3.Signer,
+  arbiter: anchor.web3.Signer | null,
+  program: Program<HandmadeNaive>
+) {
+  const txSig = await program.methods
+    .releaseEscrow()
+    .accountsPartial({
+      escrow: escrow,
+      sender: sender,
+      destinationWrappedAccount: destination_wrapped_account,
+      destinationOwner: destination_owner,
+      releaser: releaser.publicKey,
+      arbiter: arbiter ? arbiter.publicKey : null,
+      wrapperAccount: wrapper_account,
+    })
+    .signers(arbiter ? [releaser, arbiter] : [releaser])
+    .rpc();
+
+  console.log(`Release escrow tx : ${txSig}`);
+}
+
+export async function refund_escrow(
+  escrow: anchor.web3.PublicKey,
+  sender: anchor.web3.Signer,
+  source_wrapped_account: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+) {
+  const txSig = await program.methods
+    .refundEscrow()
+    .accountsPartial({
+      escrow: escrow,
+      sender: sender.publicKey,
+      sourceWrappedAccount: source_wrapped_account,
+    })
+    .signers([sender])
+    .rpc();
+
+  console.log(`Refund escrow tx : ${txSig}`);
+}
+
+// The wrappers are sorted as the program expects them
+export async function create_trust_agreement(
+  approver_1: anchor.web3.Signer,
+  wrapper_1: anchor.web3.PublicKey,
+  approver_2: anchor.web3.Signer,
+  wrapper_2: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+): Promise<anchor.web3.PublicKey> {
+  const sorted = Buffer.compare(wrapper_1.toBuffer(), wrapper_2.toBuffer()) < 0;
+  const wrapper_a = sorted ? wrapper_1 : wrapper_2;
+  const wrapper_b = sorted ? wrapper_2 : wrapper_1;
+  const approver_a = sorted ? approver_1 : approver_2;
+  const approver_b = sorted ? approver_2 : approver_1;
+  const [trust_agreement] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("trust_agreement"), wrapper_a.toBuffer(), wrapper_b.toBuffer()],
+    program.programId
+  );
+
+  const txSig = await program.methods
+    .createTrustAgreement()
+    .accountsPartial({
+      trustAgreement: trust_agreement,
+      wrapperA: wrapper_a,
+      approverA: approver_a.publicKey,
+      wrapperB: wrapper_b,
+      approverB: approver_b.publicKey,
+      payer: anchor.Wallet.local().publicKey,
+    })
+    .signers([approver_1, approver_2, anchor.Wallet.local().payer])
+    .rpc();
+
+  console.log(`Trust agreement tx : ${txSig}`);
+
+  return trust_agreement;
+}
+
+export async function revoke_trust_agreement(
+  trust_agreement: anchor.web3.PublicKey,
+  approver: anchor.web3.Signer,
+  wrapper_account: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>
+) {
+  const txSig = await program.methods
+    .revokeTrustAgreement()
+    .accountsPartial({
+      trustAgreement: trust_agreement,
+      wrapperAccount: wrapper_account,
+      approver: approver.publicKey,
+    })
+    .signers([approver])
+    .rpc();
+
+  console.log(`Revoke trust agreement tx : ${txSig}`);
+}
+
+export async function cross_wrapper_transfer(
+  amount: number,
+  trust_agreement: anchor.web3.PublicKey,
+  source: {
+    wrapper_account: anchor.web3.PublicKey;
+    approver: anchor.web3.PublicKey;
+    wrapper_token_account: anchor.web3.PublicKey;
+  },
+  destination: {
+    wrapper_account: anchor.web3.PublicKey;
+    wrapper_token_account: anchor.web3.PublicKey;
+  },
+  source_owner: anchor.web3.Signer,
+  source_wrapped_account: anchor.web3.PublicKey,
+  destination_owner: anchor.web3.PublicKey,
+  destination_wrapped_account: anchor.web3.PublicKey,
+  two_auth: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>,
+  token_program: anchor.web3.PublicKey = TOKEN_PROGRAM_ID
+) {
+  const txSig = await program.methods
+    .crossWrapperTransfer(new anchor.BN(amount), null)
+    .accountsPartial({
+      trustAgreement: trust_agreement,
+      sourceWrappedAccount: source_wrapped_account,
+      sourceOwner: source_owner.publicKey,
+      twoAuth: two_auth,
+      destinationWrappedAccount: destination_wrapped_account,
+      destinationOwner: destination_owner,
+      twoAuthSigner: null,
+      sourceWrapperAccount: source.wrapper_account,
+      sourceApprover: source.approver,
+      destinationWrapperAccount: destination.wrapper_account,
+      sourceWrapperTokenAccount: source.wrapper_token_account,
+      destinationWrapperTokenAccount: destination.wrapper_token_account,
+      mint: mint,
+      tokenProgram: token_program,
+    })
+    .signers([source_owner])
+    .rpc();
+
+  console.log(`Cross wrapper transfer of ${amount} tx : ${txSig}`);
+}
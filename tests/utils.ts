@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import fs from "fs";
 import { HandmadeNaive } from "../target/types/handmade_naive";
 import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
 
 export function load_keypair(filename: string): anchor.web3.Keypair {
   const secret = JSON.parse(fs.readFileSync(filename).toString()) as number[];
@@ -36,6 +37,22 @@ export async function sendTransaction(
   console.log(`Transfer to ${to.toBase58()} of ${amount} tx : ${signature}`);
 }
 
+export async function expect_anchor_error(
+  promise: Promise<unknown>,
+  error_code: string
+) {
+  let logs: string[] = [];
+  try {
+    await promise;
+  } catch (error) {
+    logs = error.logs ?? [];
+  }
+  expect(
+    logs.some((log) => log.includes(`Error Code: ${error_code}.`)),
+    `expected error ${error_code}`
+  ).to.be.true;
+}
+
 export async function create_user_with_best_bump(
   program: Program<HandmadeNaive>,
   mint: anchor.web3.PublicKey
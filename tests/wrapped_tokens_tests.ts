@@ -7,6 +7,7 @@ import {
   TOKEN_PROGRAM_ID,
   createAssociatedTokenAccountInstruction,
   createMint,
+  getAccount,
   getAssociatedTokenAddressSync,
   getOrCreateAssociatedTokenAccount,
 } from "@solana/spl-token";
@@ -41,3 +42,115 @@ export async function wrap_tokens(
 
   console.log(`Transfer of ${amount} tx : ${tx}`);
 }
+
+export async function deposit_for(
+  amount: number,
+  decimals: number,
+  wrapper: anchor.web3.PublicKey,
+  approver: anchor.web3.PublicKey,
+  payer: anchor.web3.Signer,
+  payer_token_account: anchor.web3.PublicKey,
+  recipient: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey,
+  wrapper_token_holder: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>,
+  tokenProgram: anchor.web3.PublicKey = TOKEN_PROGRAM_ID
+) {
+  const [recipient_wrapped_account] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("wrapped_token"), wrapper.toBuffer(), mint.toBuffer(), recipient.toBuffer()],
+    program.programId
+  );
+  const [idendity_recipient] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("identity"), recipient.toBuffer()],
+    program.programId
+  );
+
+  const tx = await program.methods
+    .depositFor(new anchor.BN(amount), decimals)
+    .accountsPartial({
+      wrapperAccount: wrapper,
+      approver: approver,
+      wrapperTokenAccount: wrapper_token_holder,
+      recipientWrappedAccount: recipient_wrapped_account,
+      idendityRecipient: idendity_recipient,
+      payerTokenAccount: payer_token_account,
+      payer: payer.publicKey,
+      mint: mint,
+      tokenProgram: tokenProgram,
+    })
+    .signers([payer])
+    .rpc();
+
+  console.log(`Deposit of ${amount} tx : ${tx}`);
+}
+
+export async function unwrap_tokens(
+  amount: number,
+  decimals: number,
+  wrapper: anchor.web3.PublicKey,
+  approver: anchor.web3.PublicKey,
+  owner: anchor.web3.Signer,
+  user_token_account: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey,
+  wrapper_token_holder: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>,
+  tokenProgram: anchor.web3.PublicKey = TOKEN_PROGRAM_ID
+) {
+  const tx = await program.methods
+    .unwrapTokens(new anchor.BN(amount), decimals)
+    .accountsPartial({
+      userTokenAccount: user_token_account,
+      owner: owner.publicKey,
+      wrapperTokenAccount: wrapper_token_holder,
+      wrapperAccount: wrapper,
+      approver: approver,
+      mint: mint,
+      twoAuthSigner: null,
+      tokenProgram: tokenProgram,
+    })
+    .signers([owner])
+    .rpc();
+
+  console.log(`Unwrap of ${amount} tx : ${tx}`);
+}
+
+export async function transfer_out(
+  amount: number,
+  wrapper: anchor.web3.PublicKey,
+  approver: anchor.web3.PublicKey,
+  owner: anchor.web3.Signer,
+  destination_token_account: anchor.web3.PublicKey,
+  idendity_receiver: anchor.web3.PublicKey | null,
+  mint: anchor.web3.PublicKey,
+  wrapper_token_holder: anchor.web3.PublicKey,
+  program: Program<HandmadeNaive>,
+  tokenProgram: anchor.web3.PublicKey = TOKEN_PROGRAM_ID
+) {
+  // The blocklist entry is derived from the owner of the destination token account
+  const destination = await getAccount(anchor.getProvider().connection, destination_token_account, undefined, tokenProgram);
+  const [receiver_blocklist_entry] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("blocklist"), wrapper.toBuffer(), destination.owner.toBuffer()],
+    program.programId
+  );
+
+  const tx = await program.methods
+    .transferOut(new anchor.BN(amount))
+    .accountsPartial({
+      wrapperAccount: wrapper,
+      approver: approver,
+      wrapperTokenAccount: wrapper_token_holder,
+      sourceOwner: owner.publicKey,
+      destinationTokenAccount: destination_token_account,
+      idendityReceiver: idendity_receiver,
+      receiverBlocklistEntry: receiver_blocklist_entry,
+      twoAuthSigner: null,
+      generalWhiteList: null,
+      priceAccount: null,
+      mint: mint,
+      tokenProgram: tokenProgram,
+    })
+    .signers([owner])
+    .rpc();
+
+  console.log(`Transfer out of ${amount} tx : ${tx}`);
+}